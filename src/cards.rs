@@ -0,0 +1,152 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Local};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Magnetic cards hold 3 tracks of this many bytes
+const TRACK_SIZE: u64 = 69;
+
+/// A .crd file the emulated card reader reads and writes
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CardFile {
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<DateTime<Local>>,
+    // Nothing has been written to the card yet
+    pub blank: bool,
+}
+impl CardFile {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let meta = fs::metadata(path)?;
+        let data = fs::read(path)?;
+        Ok(Self {
+            name: path
+                .file_name()
+                .ok_or(anyhow!("Invalid card path"))?
+                .to_string_lossy()
+                .to_string(),
+            path: path.to_path_buf(),
+            size: meta.len(),
+            modified: meta.modified().ok().map(DateTime::from),
+            blank: data.iter().all(|x| *x == 0x00 || *x == 0xFF),
+        })
+    }
+    pub fn tracks(&self) -> Option<u64> {
+        (self.size > 0 && self.size.is_multiple_of(TRACK_SIZE)).then_some(self.size / TRACK_SIZE)
+    }
+    pub fn describe(&self) -> String {
+        let mut text = if self.blank {
+            "Blank card".to_string()
+        } else {
+            match self.tracks() {
+                Some(n) => format!("{} track(s)", n),
+                None => format!("{} bytes", self.size),
+            }
+        };
+        if let Some(t) = self.modified {
+            text += &format!(",last used {}", t.format("%Y-%m-%d %H:%M"));
+        }
+        text
+    }
+}
+
+// The loader runs in the game's directory,relative card paths start from there
+pub fn card_dir(exe_path: &str) -> PathBuf {
+    Path::new(exe_path)
+        .parent()
+        .map(|x| x.to_path_buf())
+        .unwrap_or_default()
+}
+
+fn card_name(name: &str) -> anyhow::Result<String> {
+    let name = name.trim();
+    if name.is_empty() || name.contains('/') || name.starts_with('.') {
+        return Err(anyhow!("\"{}\" is not a valid card name", name));
+    }
+    if name.ends_with(".crd") {
+        Ok(name.to_string())
+    } else {
+        Ok(format!("{}.crd", name))
+    }
+}
+
+pub fn list_cards(dir: &Path) -> anyhow::Result<Vec<CardFile>> {
+    let mut cards = Vec::new();
+    for i in fs::read_dir(dir)? {
+        let path = i?.path();
+        if path.extension().is_some_and(|x| x == "crd") {
+            cards.push(CardFile::read(&path)?);
+        }
+    }
+    cards.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cards)
+}
+// A new blank card,the game issues it on first use
+pub fn create_card(dir: &Path, name: &str) -> anyhow::Result<String> {
+    let name = card_name(name)?;
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dir.join(&name))
+        .map_err(|e| anyhow!("Unable to create {}: {}", name, e))?;
+    Ok(name)
+}
+pub fn rename_card(dir: &Path, old: &str, new: &str) -> anyhow::Result<String> {
+    let new = card_name(new)?;
+    if fs::exists(dir.join(&new))? {
+        return Err(anyhow!("{} already exists", new));
+    }
+    fs::rename(dir.join(old), dir.join(&new))?;
+    Ok(new)
+}
+// Copies the card into ./backup/ next to it,stamped with the time
+pub fn backup_card(card: &CardFile) -> anyhow::Result<PathBuf> {
+    let dir = card
+        .path
+        .parent()
+        .ok_or(anyhow!("Invalid card path"))?
+        .join("backup");
+    fs::create_dir_all(&dir)?;
+    let stem = card.name.trim_end_matches(".crd");
+    let target = dir.join(format!(
+        "{}-{}.crd",
+        stem,
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::copy(&card.path, &target)?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cards() {
+        let dir = std::env::temp_dir().join("loader-gui-test-cards");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(create_card(&dir, "Card_01").unwrap(), "Card_01.crd");
+        assert!(create_card(&dir, "Card_01.crd").is_err());
+        assert!(create_card(&dir, "../x").is_err());
+        fs::write(dir.join("Card_02.crd"), [0x41; 207]).unwrap();
+        assert!(rename_card(&dir, "Card_01.crd", "Card_02").is_err());
+        assert_eq!(
+            rename_card(&dir, "Card_01.crd", "Mine").unwrap(),
+            "Mine.crd"
+        );
+
+        let cards = list_cards(&dir).unwrap();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].name, "Card_02.crd");
+        assert_eq!(cards[0].tracks(), Some(3));
+        assert!(!cards[0].blank);
+        assert!(cards[1].blank);
+        let backup = backup_card(&cards[0]).unwrap();
+        assert_eq!(fs::read(backup).unwrap().len(), 207);
+        // Backups don't show up as cards
+        assert_eq!(list_cards(&dir).unwrap().len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{
+    display::{DisplayMode, DisplayTarget},
     games::GameTitle,
-    ui::{egui_key_to_keycode, egui_keycode_to_key},
+    keycode::SdlKey,
+    link::LinkKind,
 };
 use anyhow::{Ok, anyhow};
 use eframe::egui;
@@ -11,6 +13,9 @@ use std::{
     path::Path,
 };
 
+// Settings only the GUI reads,kept in comments so the loader skips them
+pub const GUI_KEYS: [&str; 2] = ["DISPLAY_OUTPUT", "NIC_NAME"];
+
 fn i32_to_bool(value: i32) -> Option<bool> {
     match value {
         0 => Some(false),
@@ -109,9 +114,125 @@ pub struct _Keymap<T: Clone + PartialEq> {
     pub button8: Option<T>,
 }
 
-pub type SdlKeymap = _Keymap<egui::Key>;
+pub type SdlKeymap = _Keymap<SdlKey>;
 pub type EvdevKeymap = _Keymap<String>;
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum KeymapField {
+    Test,
+    Service,
+    Start,
+    Coin,
+    Up,
+    Down,
+    Left,
+    Right,
+    // 1 to 8
+    Button(usize),
+}
+impl KeymapField {
+    pub const ALL: [KeymapField; 16] = [
+        Self::Test,
+        Self::Start,
+        Self::Service,
+        Self::Coin,
+        Self::Up,
+        Self::Down,
+        Self::Left,
+        Self::Right,
+        Self::Button(1),
+        Self::Button(2),
+        Self::Button(3),
+        Self::Button(4),
+        Self::Button(5),
+        Self::Button(6),
+        Self::Button(7),
+        Self::Button(8),
+    ];
+}
+impl Display for KeymapField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Button(n) => write!(f, "Button {}", n),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+impl<T: Clone + PartialEq> _Keymap<T> {
+    // None if the field is optional and not set
+    pub fn get(&self, field: KeymapField) -> Option<&T> {
+        match field {
+            KeymapField::Test => self.test.as_ref(),
+            KeymapField::Service => Some(&self.service),
+            KeymapField::Start => Some(&self.start),
+            KeymapField::Coin => self.coin.as_ref(),
+            KeymapField::Up => Some(&self.up),
+            KeymapField::Down => Some(&self.down),
+            KeymapField::Left => Some(&self.left),
+            KeymapField::Right => Some(&self.right),
+            KeymapField::Button(1) => Some(&self.button1),
+            KeymapField::Button(2) => Some(&self.button2),
+            KeymapField::Button(3) => Some(&self.button3),
+            KeymapField::Button(4) => Some(&self.button4),
+            KeymapField::Button(5) => self.button5.as_ref(),
+            KeymapField::Button(6) => self.button6.as_ref(),
+            KeymapField::Button(7) => self.button7.as_ref(),
+            KeymapField::Button(8) => self.button8.as_ref(),
+            KeymapField::Button(_) => None,
+        }
+    }
+    pub fn get_mut(&mut self, field: KeymapField) -> Option<&mut T> {
+        match field {
+            KeymapField::Test => self.test.as_mut(),
+            KeymapField::Service => Some(&mut self.service),
+            KeymapField::Start => Some(&mut self.start),
+            KeymapField::Coin => self.coin.as_mut(),
+            KeymapField::Up => Some(&mut self.up),
+            KeymapField::Down => Some(&mut self.down),
+            KeymapField::Left => Some(&mut self.left),
+            KeymapField::Right => Some(&mut self.right),
+            KeymapField::Button(1) => Some(&mut self.button1),
+            KeymapField::Button(2) => Some(&mut self.button2),
+            KeymapField::Button(3) => Some(&mut self.button3),
+            KeymapField::Button(4) => Some(&mut self.button4),
+            KeymapField::Button(5) => self.button5.as_mut(),
+            KeymapField::Button(6) => self.button6.as_mut(),
+            KeymapField::Button(7) => self.button7.as_mut(),
+            KeymapField::Button(8) => self.button8.as_mut(),
+            KeymapField::Button(_) => None,
+        }
+    }
+    pub fn set(&mut self, field: KeymapField, value: T) {
+        match field {
+            KeymapField::Test => self.test = Some(value),
+            KeymapField::Coin => self.coin = Some(value),
+            KeymapField::Button(5) => self.button5 = Some(value),
+            KeymapField::Button(6) => self.button6 = Some(value),
+            KeymapField::Button(7) => self.button7 = Some(value),
+            KeymapField::Button(8) => self.button8 = Some(value),
+            _ => {
+                if let Some(v) = self.get_mut(field) {
+                    *v = value;
+                }
+            }
+        }
+    }
+}
+impl EvdevKeymap {
+    // Optional inputs go back to unbound,the others are cleared
+    pub fn unset(&mut self, field: KeymapField) {
+        match field {
+            KeymapField::Test => self.test = None,
+            KeymapField::Coin => self.coin = None,
+            KeymapField::Button(5) => self.button5 = None,
+            KeymapField::Button(6) => self.button6 = None,
+            KeymapField::Button(7) => self.button7 = None,
+            KeymapField::Button(8) => self.button8 = None,
+            _ => self.set(field, String::new()),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub struct _EvdevInput {
     pub player1: EvdevKeymap,
@@ -282,7 +403,7 @@ impl _EvdevInput {
         }
         Ok(())
     }
-    pub fn write_to_lindbergh_conf(&self, f: &mut File) -> anyhow::Result<()> {
+    pub fn write_to_lindbergh_conf(&self, f: &mut impl Write) -> anyhow::Result<()> {
         if self.player1.test.is_none() && self.player2.test.is_none() {
             return Err(anyhow!("Unable to find test key"));
         }
@@ -365,139 +486,89 @@ impl _EvdevInput {
         Ok(())
     }
 }
-impl SdlKeymap {
-    pub fn read_from_lindbergh_conf(&mut self, buf: &str) -> anyhow::Result<()> {
-        fn result_keycode_to_key(s: &str) -> anyhow::Result<egui::Key> {
-            let r = s.parse::<u32>()?;
-            egui_keycode_to_key(r).ok_or(anyhow!("Undefined Keycode {}", r))
+#[derive(Clone, PartialEq)]
+pub struct _SdlInput {
+    pub player1: SdlKeymap,
+    pub player2: SdlKeymap,
+}
+impl _SdlInput {
+    // e.g. PLAYER_1_BUTTON_5_KEY
+    fn key_name(player: usize, field: KeymapField) -> String {
+        match field {
+            KeymapField::Test => "TEST_KEY".into(),
+            KeymapField::Button(n) => format!("PLAYER_{}_BUTTON_{}_KEY", player, n),
+            _ => format!(
+                "PLAYER_{}_{}_KEY",
+                player,
+                format!("{:?}", field).to_ascii_uppercase()
+            ),
         }
+    }
+    pub fn read_from_lindbergh_conf(&mut self, buf: &str) -> anyhow::Result<()> {
         for (cnt, i) in buf.lines().enumerate() {
             let r = i.split_whitespace().collect::<Vec<&str>>();
-            if r[0] == "#" || r.is_empty() {
+            if r.is_empty() || r[0] == "#" {
                 continue;
             }
             if r.len() < 2 {
                 return Err(anyhow!("Too few arguments on line {}", cnt + 1));
             }
-            match r[0] {
-                "TEST_KEY" => {
-                    self.test = Some(result_keycode_to_key(r[1])?);
-                }
-                "PLAYER_1_START_KEY" => {
-                    self.start = result_keycode_to_key(r[1])?;
-                }
-                "PLAYER_1_SERVICE_KEY" => {
-                    self.service = result_keycode_to_key(r[1])?;
-                }
-                "PLAYER_1_COIN_KEY" => {
-                    self.coin = Some(result_keycode_to_key(r[1])?);
-                }
-                "PLAYER_1_UP_KEY" => {
-                    self.up = result_keycode_to_key(r[1])?;
-                }
-                "PLAYER_1_DOWN_KEY" => {
-                    self.down = result_keycode_to_key(r[1])?;
-                }
-                "PLAYER_1_LEFT_KEY" => {
-                    self.left = result_keycode_to_key(r[1])?;
-                }
-                "PLAYER_1_RIGHT_KEY" => {
-                    self.right = result_keycode_to_key(r[1])?;
-                }
-                "PLAYER_1_BUTTON_1_KEY" => {
-                    self.button1 = result_keycode_to_key(r[1])?;
-                }
-                "PLAYER_1_BUTTON_2_KEY" => {
-                    self.button2 = result_keycode_to_key(r[1])?;
-                }
-                "PLAYER_1_BUTTON_3_KEY" => {
-                    self.button3 = result_keycode_to_key(r[1])?;
-                }
-                "PLAYER_1_BUTTON_4_KEY" => {
-                    self.button4 = result_keycode_to_key(r[1])?;
+            for player in 1..=2 {
+                for field in KeymapField::ALL {
+                    if Self::key_name(player, field) != r[0] {
+                        continue;
+                    }
+                    let key = SdlKey::from_keycode(r[1].parse()?);
+                    if player == 1 || field == KeymapField::Test {
+                        self.player1.set(field, key);
+                    } else {
+                        self.player2.set(field, key);
+                    }
                 }
-                _ => {}
             }
         }
         Ok(())
     }
-    pub fn write_to_lindbergh_conf(&self, f: &mut File) -> anyhow::Result<()> {
-        if self.test.is_none() {
+    pub fn write_to_lindbergh_conf(&self, f: &mut impl Write) -> anyhow::Result<()> {
+        if self.player1.test.is_none() {
             return Err(anyhow!("Cannot find test key"));
         }
-        if self.coin.is_none() {
+        if self.player1.coin.is_none() {
             return Err(anyhow!("Cannot find coin key"));
         }
-        fn result_key_to_keycode(key: &egui::Key) -> anyhow::Result<u32> {
-            egui_key_to_keycode(key).ok_or(anyhow!("Cannot find corresponding keycode to key!"))
+        fn result_key_to_keycode(key: &SdlKey) -> anyhow::Result<u32> {
+            key.keycode().ok_or(anyhow!(
+                "Cannot find corresponding keycode to key {}!",
+                key.name()
+            ))
+        }
+        for (player, keymap) in [(1, &self.player1), (2, &self.player2)] {
+            for field in KeymapField::ALL {
+                if player == 2 && field == KeymapField::Test {
+                    continue;
+                }
+                // Optional keys are only written when they are set
+                if let Some(key) = keymap.get(field) {
+                    writeln!(
+                        f,
+                        "{} {}",
+                        Self::key_name(player, field),
+                        result_key_to_keycode(key)?
+                    )?;
+                }
+            }
         }
-        writeln!(
-            f,
-            "TEST_KEY {}",
-            result_key_to_keycode(self.test.as_ref().unwrap())?
-        )?;
-        writeln!(
-            f,
-            "PLAYER_1_START_KEY {}",
-            result_key_to_keycode(&self.start)?
-        )?;
-        writeln!(
-            f,
-            "PLAYER_1_SERVICE_KEY {}",
-            result_key_to_keycode(&self.service)?
-        )?;
-        writeln!(
-            f,
-            "PLAYER_1_COIN_KEY {}",
-            result_key_to_keycode(self.coin.as_ref().unwrap())?
-        )?;
-        writeln!(f, "PLAYER_1_UP_KEY {}", result_key_to_keycode(&self.up)?)?;
-        writeln!(
-            f,
-            "PLAYER_1_DOWN_KEY {}",
-            result_key_to_keycode(&self.down)?
-        )?;
-        writeln!(
-            f,
-            "PLAYER_1_LEFT_KEY {}",
-            result_key_to_keycode(&self.left)?
-        )?;
-        writeln!(
-            f,
-            "PLAYER_1_RIGHT_KEY {}",
-            result_key_to_keycode(&self.right)?
-        )?;
-        writeln!(
-            f,
-            "PLAYER_1_BUTTON_1_KEY {}",
-            result_key_to_keycode(&self.button1)?
-        )?;
-        writeln!(
-            f,
-            "PLAYER_1_BUTTON_2_KEY {}",
-            result_key_to_keycode(&self.button2)?
-        )?;
-        writeln!(
-            f,
-            "PLAYER_1_BUTTON_3_KEY {}",
-            result_key_to_keycode(&self.button3)?
-        )?;
-        writeln!(
-            f,
-            "PLAYER_1_BUTTON_4_KEY {}",
-            result_key_to_keycode(&self.button4)?
-        )?;
         Ok(())
     }
 }
 // NOTE: Use this in other module only
+// There's only one of these per config,boxing the halves isn't worth it
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, PartialEq)]
 pub enum Keymap {
-    // SDL/X11 input does not support second player by far
-    // NOTE: This should be changed when lindbergh-loader supports two players
-    Sdl(SdlKeymap),
+    Sdl(_SdlInput),
     Evdev(_EvdevInput),
-    Both(SdlKeymap, _EvdevInput),
+    Both(_SdlInput, _EvdevInput),
 }
 impl Debug for Keymap {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -517,14 +588,14 @@ impl Keymap {
         }
     }
     // use this before check
-    pub fn get_sdlkeymap(&self) -> Option<&SdlKeymap> {
+    pub fn get_sdl(&self) -> Option<&_SdlInput> {
         match self {
             Self::Sdl(s) => Some(s),
             Self::Both(s, _) => Some(s),
             _ => None,
         }
     }
-    pub fn get_sdlkeymap_mut(&mut self) -> Option<&mut SdlKeymap> {
+    pub fn get_sdl_mut(&mut self) -> Option<&mut _SdlInput> {
         match self {
             Self::Sdl(s) => Some(s),
             Self::Both(s, _) => Some(s),
@@ -548,14 +619,14 @@ impl Keymap {
     pub fn into_both(self) -> Self {
         match self {
             Self::Sdl(s) => Self::Both(s, _EvdevInput::default()),
-            Self::Evdev(e) => Self::Both(SdlKeymap::default(), e),
+            Self::Evdev(e) => Self::Both(_SdlInput::default(), e),
             Self::Both(_, _) => self,
         }
     }
     pub fn into_sdl(self) -> Self {
         match self {
             Self::Sdl(_) => self,
-            Self::Evdev(_) => Self::Sdl(SdlKeymap::default()),
+            Self::Evdev(_) => Self::Sdl(_SdlInput::default()),
             Self::Both(s, _) => Self::Sdl(s),
         }
     }
@@ -575,7 +646,7 @@ impl Keymap {
     pub fn has_evdev(&self) -> bool {
         matches!(self, Keymap::Evdev(_)) || self.has_both()
     }
-    pub fn write_to_lindbergh_conf(&self, f: &mut File) -> anyhow::Result<()> {
+    pub fn write_to_lindbergh_conf(&self, f: &mut impl Write) -> anyhow::Result<()> {
         match self {
             Self::Sdl(s) => s.write_to_lindbergh_conf(f)?,
             Self::Evdev(e) => e.write_to_lindbergh_conf(f)?,
@@ -601,14 +672,14 @@ impl Keymap {
             if r[0] == "INPUT_MODE" {
                 match r[1].parse::<u32>()? {
                     0 => {
-                        let mut s = SdlKeymap::default();
+                        let mut s = _SdlInput::default();
                         s.read_from_lindbergh_conf(buf)?;
                         let mut e = _EvdevInput::default();
                         e.read_from_lindbergh_conf(buf)?;
                         *self = Self::Both(s, e);
                     }
                     1 => {
-                        let mut s = SdlKeymap::default();
+                        let mut s = _SdlInput::default();
                         s.read_from_lindbergh_conf(buf)?;
                         *self = Self::Sdl(s);
                     }
@@ -626,37 +697,158 @@ impl Keymap {
         Ok(())
     }
 }
+
+/// Where a key is bound,used to report conflicts
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BindingSlot {
+    pub evdev: bool,
+    pub player: usize,
+    pub field: KeymapField,
+}
+impl Display for BindingSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let method = if self.evdev { "Evdev" } else { "SDL" };
+        if self.field == KeymapField::Test {
+            write!(f, "{} Test", method)
+        } else {
+            write!(f, "{} Player {} {}", method, self.player, self.field)
+        }
+    }
+}
+impl BindingSlot {
+    // The slots sharing a key with this one,out of Keymap::conflicts()
+    pub fn conflicts_in(&self, conflicts: &[(BindingSlot, BindingSlot)]) -> Vec<BindingSlot> {
+        conflicts
+            .iter()
+            .filter_map(|(a, b)| {
+                if a == self {
+                    Some(*b)
+                } else if b == self {
+                    Some(*a)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+impl Keymap {
+    // Pairs of inputs sharing a key,SDL and evdev are checked separately
+    pub fn conflicts(&self) -> Vec<(BindingSlot, BindingSlot)> {
+        fn pairs<T: PartialEq>(bindings: &[(BindingSlot, &T)]) -> Vec<(BindingSlot, BindingSlot)> {
+            let mut r = Vec::new();
+            for (cnt, a) in bindings.iter().enumerate() {
+                for b in &bindings[cnt + 1..] {
+                    if a.1 == b.1 {
+                        r.push((a.0, b.0));
+                    }
+                }
+            }
+            r
+        }
+        let mut r = Vec::new();
+        if let Some(sdl) = self.get_sdl() {
+            let mut bindings = Vec::new();
+            for (player, keymap) in [(1, &sdl.player1), (2, &sdl.player2)] {
+                for field in KeymapField::ALL {
+                    if let Some(key) = keymap.get(field) {
+                        let evdev = false;
+                        bindings.push((
+                            BindingSlot {
+                                evdev,
+                                player,
+                                field,
+                            },
+                            key,
+                        ));
+                    }
+                }
+            }
+            r.extend(pairs(&bindings));
+        }
+        if let Some(e) = self.get_evdev() {
+            let mut bindings = Vec::new();
+            for (player, keymap) in [(1, &e.player1), (2, &e.player2)] {
+                for field in KeymapField::ALL {
+                    // Not written for evdev
+                    if field == KeymapField::Coin || (player == 2 && field == KeymapField::Test) {
+                        continue;
+                    }
+                    if let Some(key) = keymap.get(field).filter(|x| !x.is_empty()) {
+                        let evdev = true;
+                        bindings.push((
+                            BindingSlot {
+                                evdev,
+                                player,
+                                field,
+                            },
+                            key,
+                        ));
+                    }
+                }
+            }
+            r.extend(pairs(&bindings));
+        }
+        r
+    }
+}
 impl Default for Keymap {
     fn default() -> Self {
-        Keymap::Both(SdlKeymap::default(), _EvdevInput::default())
+        Keymap::Both(_SdlInput::default(), _EvdevInput::default())
     }
 }
 impl Default for SdlKeymap {
     fn default() -> Self {
         Self {
-            test: Some(egui::Key::T),
-            service: egui::Key::S,
-            start: egui::Key::Num1,
-            coin: Some(egui::Key::Num5),
-            up: egui::Key::ArrowUp,
-            down: egui::Key::ArrowDown,
-            left: egui::Key::ArrowLeft,
-            right: egui::Key::ArrowRight,
-            button1: egui::Key::Q,
-            button2: egui::Key::W,
-            button3: egui::Key::E,
-            button4: egui::Key::R,
-            button5: None,
-            button6: None,
-            button7: None,
-            button8: None,
+            test: Some(egui::Key::T.into()),
+            service: egui::Key::S.into(),
+            start: egui::Key::Num1.into(),
+            coin: Some(egui::Key::Num5.into()),
+            up: egui::Key::ArrowUp.into(),
+            down: egui::Key::ArrowDown.into(),
+            left: egui::Key::ArrowLeft.into(),
+            right: egui::Key::ArrowRight.into(),
+            button1: egui::Key::Q.into(),
+            button2: egui::Key::W.into(),
+            button3: egui::Key::E.into(),
+            button4: egui::Key::R.into(),
+            button5: Some(egui::Key::Z.into()),
+            button6: Some(egui::Key::X.into()),
+            button7: Some(egui::Key::C.into()),
+            button8: Some(egui::Key::V.into()),
+        }
+    }
+}
+impl Default for _SdlInput {
+    fn default() -> Self {
+        Self {
+            player1: SdlKeymap::default(),
+            // Test key follows player 1's
+            player2: SdlKeymap {
+                test: None,
+                service: egui::Key::D.into(),
+                start: egui::Key::Num2.into(),
+                coin: Some(egui::Key::Num6.into()),
+                up: egui::Key::I.into(),
+                down: egui::Key::K.into(),
+                left: egui::Key::J.into(),
+                right: egui::Key::L.into(),
+                button1: egui::Key::Y.into(),
+                button2: egui::Key::U.into(),
+                button3: egui::Key::O.into(),
+                button4: egui::Key::P.into(),
+                button5: Some(egui::Key::H.into()),
+                button6: Some(egui::Key::N.into()),
+                button7: Some(egui::Key::M.into()),
+                button8: Some(egui::Key::B.into()),
+            },
         }
     }
 }
 impl Default for EvdevKeymap {
     fn default() -> Self {
         Self {
-            test: Some("AT_TRANSLATED_SET_2_KEYBOARD_KEY_1".into()),
+            test: Some("AT_TRANSLATED_SET_2_KEYBOARD_KEY_F2".into()),
             start: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_1".into(),
             coin: None,
             service: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_S".into(),
@@ -686,7 +878,25 @@ impl Default for _EvdevInput {
             ],
             analogue_deadzones: [(0, 0, 0); 8],
             player1: EvdevKeymap::default(),
-            player2: EvdevKeymap::default(),
+            // Test key follows player 1's
+            player2: EvdevKeymap {
+                test: None,
+                start: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_2".into(),
+                coin: None,
+                service: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_D".into(),
+                up: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_KP8".into(),
+                down: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_KP2".into(),
+                left: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_KP4".into(),
+                right: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_KP6".into(),
+                button1: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_Z".into(),
+                button2: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_X".into(),
+                button3: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_C".into(),
+                button4: "AT_TRANSLATED_SET_2_KEYBOARD_KEY_V".into(),
+                button5: Some("AT_TRANSLATED_SET_2_KEYBOARD_KEY_B".into()),
+                button6: Some("AT_TRANSLATED_SET_2_KEYBOARD_KEY_N".into()),
+                button7: Some("AT_TRANSLATED_SET_2_KEYBOARD_KEY_M".into()),
+                button8: Some("AT_TRANSLATED_SET_2_KEYBOARD_KEY_COMMA".into()),
+            },
         }
     }
 }
@@ -706,6 +916,8 @@ pub struct LindberghConfig {
     // width,height
     pub window_size: (u32, u32),
     pub fullscreen: bool,
+    // Not read by the loader,the GUI sets the game's environment from it
+    pub display: DisplayTarget,
     pub disable_sdl: bool,
     pub game_region: GameRegion,
     pub freeplay: bool,
@@ -753,6 +965,7 @@ impl PartialEq for LindberghConfig {
         self.exe_path == other.exe_path
             && self.window_size == other.window_size
             && self.fullscreen == other.fullscreen
+            && self.display == other.display
             && self.disable_sdl == other.disable_sdl
             && self.game_region == other.game_region
             && self.freeplay == other.freeplay
@@ -791,6 +1004,9 @@ impl PartialEq for LindberghConfig {
             && self.harley_cab == other.harley_cab
             && self.outrun_link_ip == other.outrun_link_ip
             && self.cpu_freq == other.cpu_freq
+            && self.input_method == other.input_method
+            && self.emulate_cardreader == other.emulate_cardreader
+            && self.card_file == other.card_file
     }
 }
 impl Default for LindberghConfig {
@@ -799,6 +1015,7 @@ impl Default for LindberghConfig {
             exe_path: String::new(),
             window_size: (640, 480),
             fullscreen: false,
+            display: DisplayTarget::default(),
             freeplay: false,
             disable_sdl: false,
             input_method: Keymap::default(),
@@ -850,16 +1067,57 @@ impl Default for LindberghConfig {
 }
 impl LindberghConfig {
     pub fn write_to_lindbergh_conf(&self, current_title: &GameTitle) -> anyhow::Result<()> {
-        let path = format!("./config/{:?}.conf", current_title);
-        if !fs::exists(&path)? {
-            File::create_new(&path)?;
+        self.write_to_lindbergh_conf_by_path(
+            format!("./config/{:?}.conf", current_title),
+            current_title,
+        )
+    }
+    /**
+     * The config is written into <path>.tmp first and read back to make sure it's vaild,
+     * then the old file is kept as <path>.bak and the new one is renamed into place.
+     * So whatever happens,<path> is either the old config or the new one.
+     */
+    pub fn write_to_lindbergh_conf_by_path(
+        &self,
+        path: impl AsRef<Path>,
+        current_title: &GameTitle,
+    ) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let tmp_path = path.with_extension("conf.tmp");
+        let buf = self.to_lindbergh_conf_string(current_title)?;
+        {
+            let mut f = File::create(&tmp_path)?;
+            f.write_all(buf.as_bytes())?;
+            f.sync_all()?;
+        }
+        if let Err(e) = LindberghConfig::default().read_from_lindbergh_conf_by_path(&tmp_path) {
+            fs::remove_file(&tmp_path)?;
+            return Err(anyhow!("Generated config cannot be read back:\n{}", e));
+        }
+        if fs::exists(path)? {
+            fs::copy(path, path.with_extension("conf.bak"))?;
         }
-        let mut f = File::options().write(true).truncate(true).open(&path)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+    pub fn to_lindbergh_conf_string(&self, current_title: &GameTitle) -> anyhow::Result<String> {
+        let mut f: Vec<u8> = Vec::new();
 
         // NOTE: Properties that are commented out are currently in-dev settings that might supported in the future
         // NOTE: It might also be deprecated in the future
 
         writeln!(f, "# {}", self.exe_path)?;
+        writeln!(f, "# FORMAT_VERSION {}", migration::FORMAT_VERSION)?;
+        if !self.display.output.is_empty() {
+            write!(f, "# DISPLAY_OUTPUT {}", self.display.output)?;
+            if let Some(mode) = self.display.mode {
+                write!(f, " {}", mode)?;
+            }
+            writeln!(f)?;
+        }
+        if !self.nic_name.is_empty() {
+            writeln!(f, "# NIC_NAME {}", self.nic_name)?;
+        }
         writeln!(f, "# This file is generated by lindbergh-loader-gui")?;
         writeln!(
             f,
@@ -940,22 +1198,32 @@ impl LindberghConfig {
             writeln!(f, "CUSTOM_CURSOR_WIDTH {}", self.custom_cursor_width)?;
             writeln!(f, "CUSTOM_CURSOR_HEIGHT {}", self.custom_cursor_height)?;
         }
-        // writeln!(f, "ID_IP_SEAT_1 {}", self.id45_ip_seat[0])?;
-        // writeln!(f, "ID_IP_SEAT_2 {}", self.id45_ip_seat[1])?;
+        match LinkKind::from_title(current_title) {
+            Some(LinkKind::InitialD) => {
+                writeln!(f, "ID_IP_SEAT_1 {}", self.id45_ip_seat[0])?;
+                writeln!(f, "ID_IP_SEAT_2 {}", self.id45_ip_seat[1])?;
+            }
+            Some(LinkKind::Harley) => {
+                for i in 1..=4 {
+                    // Cabinets that aren't linked are left out
+                    if !self.harley_cab[i - 1].is_empty() {
+                        writeln!(f, "HARLEY_CAB{} {}", i, self.harley_cab[i - 1])?;
+                    }
+                }
+            }
+            _ => {}
+        }
         writeln!(f, "OR2_IP {}", self.outrun_link_ip)?;
-        // for i in 1..=4 {
-        //     writeln!(f, "HARLEY_CAB{} {}", i, self.harley_cab[i - 1])?;
-        // }
         if self.cpu_freq > 0.0 {
             writeln!(f, "CPU_FREQ_GHZ {}", self.cpu_freq)?;
         }
-        if current_title == &GameTitle::Virtua_Tennis_3 {
+        if current_title.uses_card_reader() {
             writeln!(f, "EMULATE_CARDREADER {}", self.emulate_cardreader as i32)?;
             writeln!(f, "CARDFILE_01 {}", self.card_file[0])?;
             writeln!(f, "CARDFILE_02 {}", self.card_file[1])?;
         }
         self.input_method.write_to_lindbergh_conf(&mut f)?;
-        Ok(())
+        Ok(String::from_utf8(f)?)
     }
     pub fn read_from_lindbergh_conf(&mut self, buf: &str) -> anyhow::Result<()> {
         fn result_i32_to_bool(value: i32, line: usize) -> anyhow::Result<bool> {
@@ -964,6 +1232,7 @@ impl LindberghConfig {
                 line
             ))
         }
+        let buf = &migration::migrate(buf)?;
         self.input_method.read_from_lindbergh_conf(buf)?;
         for (cnt, i) in buf.lines().enumerate() {
             if cnt == 0 {
@@ -977,6 +1246,17 @@ impl LindberghConfig {
             if r.is_empty() {
                 continue;
             }
+            if r.len() >= 3 && r[0] == "#" && r[1] == "DISPLAY_OUTPUT" {
+                self.display = DisplayTarget {
+                    output: r[2].to_string(),
+                    mode: r.get(3).map(|x| DisplayMode::parse(x)).transpose()?,
+                };
+                continue;
+            }
+            if r.len() >= 3 && r[0] == "#" && r[1] == "NIC_NAME" {
+                self.nic_name = r[2].to_string();
+                continue;
+            }
             if r[0] == "#" {
                 continue;
             }
@@ -1186,6 +1466,8 @@ impl LindberghConfig {
                     self.outrun_link_ip = r[1].to_string();
                 }
                 "HARLEY_CAB1" => {
+                    // The cabinets listed after it are the whole link
+                    self.harley_cab = Default::default();
                     self.harley_cab[0] = r[1].to_string();
                 }
                 "HARLEY_CAB2" => {
@@ -1197,7 +1479,7 @@ impl LindberghConfig {
                 "HARLEY_CAB4" => {
                     self.harley_cab[3] = r[1].to_string();
                 }
-                "CPU_FREQ" => {
+                "CPU_FREQ_GHZ" => {
                     self.cpu_freq = r[1].parse()?;
                 }
                 "EMULATE_CARDREADER" => {
@@ -1230,6 +1512,109 @@ impl LindberghConfig {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_is_atomic() {
+        let dir =
+            std::env::temp_dir().join(format!("loader-gui-test-write-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Virtua_Fighter_5.conf");
+        let title = GameTitle::Virtua_Fighter_5;
+        let config = LindberghConfig::default();
+        config
+            .write_to_lindbergh_conf_by_path(&path, &title)
+            .unwrap();
+        let old = read_to_string(&path).unwrap();
+
+        let mut broken = config.clone();
+        broken.input_method.get_sdl_mut().unwrap().player1.coin = None;
+        assert!(
+            broken
+                .write_to_lindbergh_conf_by_path(&path, &title)
+                .is_err()
+        );
+        assert_eq!(read_to_string(&path).unwrap(), old);
+        assert!(!fs::exists(path.with_extension("conf.tmp")).unwrap());
+
+        let mut changed = config.clone();
+        changed.fullscreen = true;
+        changed
+            .write_to_lindbergh_conf_by_path(&path, &title)
+            .unwrap();
+        assert_eq!(
+            read_to_string(path.with_extension("conf.bak")).unwrap(),
+            old
+        );
+        let new = read_to_string(&path).unwrap();
+        assert!(new.contains("FULLSCREEN 1"));
+
+        // Written fine,but "SRAM_PATH " can't be read back
+        let mut unreadable = changed.clone();
+        unreadable.sram_path = String::new();
+        let e = unreadable
+            .write_to_lindbergh_conf_by_path(&path, &title)
+            .unwrap_err();
+        assert!(
+            e.to_string()
+                .starts_with("Generated config cannot be read back")
+        );
+        assert_eq!(read_to_string(&path).unwrap(), new);
+        assert_eq!(
+            read_to_string(path.with_extension("conf.bak")).unwrap(),
+            old
+        );
+        assert!(!fs::exists(path.with_extension("conf.tmp")).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut keymap = Keymap::default();
+        assert!(keymap.conflicts().is_empty());
+        keymap.get_sdl_mut().unwrap().player2.button1 = egui::Key::Q.into();
+        let slot = |player, field| BindingSlot {
+            evdev: false,
+            player,
+            field,
+        };
+        assert_eq!(
+            slot(2, KeymapField::Button(1)).conflicts_in(&keymap.conflicts()),
+            vec![slot(1, KeymapField::Button(1))]
+        );
+        // Unbound evdev inputs don't clash with each other
+        let evdev = keymap.get_evdev_mut().unwrap();
+        evdev.player1.button1 = "".into();
+        evdev.player1.button2 = "".into();
+        assert_eq!(keymap.conflicts().len(), 1);
+        let evdev = keymap.get_evdev_mut().unwrap();
+        evdev.player1.button2 = evdev.player1.button3.clone();
+        let conflicts = keymap.conflicts();
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts[1].0.evdev && conflicts[1].1.evdev);
+    }
+
+    #[test]
+    fn test_sdl_second_player() {
+        let mut sdl = _SdlInput::default();
+        sdl.player2.button8 = None;
+        let mut buf = Vec::new();
+        sdl.write_to_lindbergh_conf(&mut buf).unwrap();
+        let buf = String::from_utf8(buf).unwrap();
+        assert!(buf.contains("PLAYER_2_BUTTON_1_KEY"));
+        assert!(!buf.contains("PLAYER_2_BUTTON_8_KEY"));
+        assert!(!buf.contains("PLAYER_2_TEST_KEY"));
+
+        let mut read = _SdlInput::default();
+        read.player2.up = egui::Key::A.into();
+        read.read_from_lindbergh_conf(&buf).unwrap();
+        assert!(read.player2.up == egui::Key::I.into());
+        assert!(read.player1.button5 == Some(egui::Key::Z.into()));
+    }
+}
+
 pub mod executable_path {
     use crate::games::{GameData, GameTitle};
     use anyhow::*;
@@ -1254,3 +1639,332 @@ pub mod executable_path {
         Ok(game_library)
     }
 }
+
+/**
+ * Every generated config has a "# FORMAT_VERSION n" line right after the executable path.
+ * Configs without it (older lindbergh-loader-gui or docs/lindbergh.conf) are version 1.
+ * When the format changes,bump FORMAT_VERSION and append a migration to MIGRATIONS.
+ */
+pub mod migration {
+    use anyhow::anyhow;
+
+    pub const FORMAT_VERSION: u32 = 2;
+
+    type Migration = fn(&str) -> String;
+    // (version it upgrades from, migration)
+    const MIGRATIONS: [(u32, Migration); 1] = [(1, migrate_v1_to_v2)];
+
+    pub fn format_version(buf: &str) -> anyhow::Result<u32> {
+        for i in buf.lines() {
+            let r = i.split_whitespace().collect::<Vec<&str>>();
+            if r.len() >= 3 && r[0] == "#" && r[1] == "FORMAT_VERSION" {
+                return Ok(r[2].parse()?);
+            }
+        }
+        Ok(1)
+    }
+
+    pub fn migrate(buf: &str) -> anyhow::Result<String> {
+        let version = format_version(buf)?;
+        if version > FORMAT_VERSION {
+            return Err(anyhow!(
+                "Config format version {} is newer than the supported version {}",
+                version,
+                FORMAT_VERSION
+            ));
+        }
+        let mut buf = buf.to_string();
+        for (from, migration) in MIGRATIONS.iter() {
+            if version <= *from {
+                buf = migration(&buf);
+            }
+        }
+        Ok(buf)
+    }
+
+    /**
+     * - CPU_FREQ -> CPU_FREQ_GHZ
+     * - LINDBERGH_COLOR -> LINDBERGH_COLOUR
+     * - "# ID_IP_SEAT_n" and "# HARLEY_CABn" are uncommented
+     * - true/false -> 1/0
+     * - GPU_VENDOR names -> numbers
+     */
+    fn migrate_v1_to_v2(buf: &str) -> String {
+        let mut lines = Vec::new();
+        for (cnt, i) in buf.lines().enumerate() {
+            // The first line is always the executable path
+            if cnt == 0 {
+                lines.push(i.to_string());
+                continue;
+            }
+            let mut r = i.split_whitespace().collect::<Vec<&str>>();
+            if r.len() >= 3
+                && r[0] == "#"
+                && (r[1].starts_with("ID_IP_SEAT_") || r[1].starts_with("HARLEY_CAB"))
+            {
+                r.remove(0);
+            }
+            if r.len() < 2 || r[0].starts_with('#') {
+                lines.push(i.to_string());
+                continue;
+            }
+            let key = match r[0] {
+                "CPU_FREQ" => "CPU_FREQ_GHZ",
+                "LINDBERGH_COLOR" => "LINDBERGH_COLOUR",
+                k => k,
+            };
+            let value = match (key, r[1].to_ascii_uppercase().as_str()) {
+                (_, "TRUE") => "1".to_string(),
+                (_, "FALSE") => "0".to_string(),
+                ("GPU_VENDOR", "AUTO_DETECT") => "0".to_string(),
+                ("GPU_VENDOR", "NVIDIA") => "1".to_string(),
+                ("GPU_VENDOR", "AMD") => "2".to_string(),
+                ("GPU_VENDOR", "ATI") => "3".to_string(),
+                ("GPU_VENDOR", "INTEL") => "4".to_string(),
+                ("GPU_VENDOR", "UNKNOWN") => "5".to_string(),
+                _ => r[1..].join(" "),
+            };
+            lines.push(format!("{} {}", key, value));
+        }
+        lines.join("\n")
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_migrate_v1() {
+            let old = "# /path/to/game.elf\nCPU_FREQ 3.2\nLINDBERGH_COLOR RED\nFULLSCREEN true\nGPU_VENDOR NVIDIA\n# ID_IP_SEAT_1 192.168.1.2\n# just a comment\n";
+            assert_eq!(format_version(old).unwrap(), 1);
+            assert_eq!(
+                migrate(old).unwrap(),
+                "# /path/to/game.elf\nCPU_FREQ_GHZ 3.2\nLINDBERGH_COLOUR RED\nFULLSCREEN 1\nGPU_VENDOR 1\nID_IP_SEAT_1 192.168.1.2\n# just a comment"
+            );
+            let new = format!(
+                "# /path/to/game.elf\n# FORMAT_VERSION {}\nCPU_FREQ 3.2",
+                FORMAT_VERSION
+            );
+            assert_eq!(migrate(&new).unwrap(), new);
+            assert!(migrate("# /path\n# FORMAT_VERSION 999").is_err());
+        }
+    }
+}
+
+pub mod diff {
+    use std::fmt::Display;
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub enum ConfChange {
+        Added {
+            key: String,
+            value: String,
+        },
+        Removed {
+            key: String,
+            value: String,
+        },
+        Modified {
+            key: String,
+            old: String,
+            new: String,
+        },
+    }
+    impl Display for ConfChange {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::Added { key, value } => write!(f, "+ {} {}", key, value),
+                Self::Removed { key, value } => write!(f, "- {} {}", key, value),
+                Self::Modified { key, old, new } => write!(f, "~ {} {} -> {}", key, old, new),
+            }
+        }
+    }
+
+    // Comments and blank lines are ignored,the rest of the line after the key is the value
+    // The GUI's own settings are kept in comments,those are compared too
+    pub fn parse_entries(buf: &str) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        for i in buf.lines() {
+            let i = i.trim();
+            let i = i
+                .strip_prefix("# ")
+                .filter(|x| {
+                    super::GUI_KEYS
+                        .iter()
+                        .any(|k| x.starts_with(&format!("{} ", k)))
+                })
+                .unwrap_or(i);
+            if i.is_empty() || i.starts_with('#') {
+                continue;
+            }
+            let (key, value) = i.split_once(char::is_whitespace).unwrap_or((i, ""));
+            entries.push((key.to_string(), value.trim().to_string()));
+        }
+        entries
+    }
+
+    pub fn diff_conf(old: &str, new: &str) -> Vec<ConfChange> {
+        let old = parse_entries(old);
+        let new = parse_entries(new);
+        let mut changes = Vec::new();
+        for (key, value) in new.iter() {
+            match old.iter().find(|(k, _)| k == key) {
+                Some((_, v)) if v == value => {}
+                Some((_, v)) => changes.push(ConfChange::Modified {
+                    key: key.clone(),
+                    old: v.clone(),
+                    new: value.clone(),
+                }),
+                None => changes.push(ConfChange::Added {
+                    key: key.clone(),
+                    value: value.clone(),
+                }),
+            }
+        }
+        for (key, value) in old.iter() {
+            if !new.iter().any(|(k, _)| k == key) {
+                changes.push(ConfChange::Removed {
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+        changes
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_diff_conf() {
+            let old = "# /path/to/game.elf\nWIDTH 640\nHEIGHT 480\nFREEPLAY 0\n";
+            let new = "# /path/to/game.elf\nWIDTH 800\nHEIGHT 480\n\nHIDE_CURSOR 1\n";
+            assert_eq!(
+                diff_conf(old, new),
+                vec![
+                    ConfChange::Modified {
+                        key: "WIDTH".into(),
+                        old: "640".into(),
+                        new: "800".into()
+                    },
+                    ConfChange::Added {
+                        key: "HIDE_CURSOR".into(),
+                        value: "1".into()
+                    },
+                    ConfChange::Removed {
+                        key: "FREEPLAY".into(),
+                        value: "0".into()
+                    },
+                ]
+            );
+            assert!(diff_conf(new, new).is_empty());
+        }
+    }
+}
+
+/// Named keymaps kept in ./config/presets/<name>.preset,independent of any game
+/// A preset is the INPUT_MODE line and the input keys of a lindbergh config
+pub mod presets {
+    use super::*;
+    use std::path::PathBuf;
+
+    const PRESET_DIR: &str = "./config/presets";
+
+    pub fn preset_path(name: &str) -> anyhow::Result<PathBuf> {
+        let name = name.trim();
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(anyhow!("Invaild preset name \"{}\"", name));
+        }
+        Ok(Path::new(PRESET_DIR).join(format!("{}.preset", name)))
+    }
+    pub fn list_presets() -> anyhow::Result<Vec<String>> {
+        if !fs::exists(PRESET_DIR)? {
+            return Ok(vec![]);
+        }
+        let mut list = Vec::new();
+        for i in fs::read_dir(PRESET_DIR)? {
+            let path = i?.path();
+            if path.extension().is_some_and(|x| x == "preset")
+                && let Some(stem) = path.file_stem()
+            {
+                list.push(stem.to_string_lossy().to_string());
+            }
+        }
+        list.sort();
+        Ok(list)
+    }
+    pub fn write_preset_file(path: impl AsRef<Path>, keymap: &Keymap) -> anyhow::Result<()> {
+        let mut buf = Vec::new();
+        writeln!(buf, "INPUT_MODE {}", keymap.as_i32())?;
+        keymap.write_to_lindbergh_conf(&mut buf)?;
+        fs::write(path, buf)?;
+        Ok(())
+    }
+    // An existing preset is never replaced,the user picks another name instead
+    pub fn create_preset_file(path: impl AsRef<Path>, keymap: &Keymap) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        if fs::exists(path)? {
+            return Err(anyhow!(
+                "A preset named \"{}\" already exists",
+                path.file_stem().unwrap_or_default().to_string_lossy()
+            ));
+        }
+        write_preset_file(path, keymap)
+    }
+    pub fn read_preset_file(path: impl AsRef<Path>) -> anyhow::Result<Keymap> {
+        let buf = read_to_string(path)?;
+        if !buf.lines().any(|x| x.starts_with("INPUT_MODE")) {
+            return Err(anyhow!("Not a keymap preset: INPUT_MODE is missing"));
+        }
+        let mut keymap = Keymap::default();
+        keymap.read_from_lindbergh_conf(&buf)?;
+        Ok(keymap)
+    }
+    pub fn save_preset(name: &str, keymap: &Keymap) -> anyhow::Result<()> {
+        fs::create_dir_all(PRESET_DIR)?;
+        create_preset_file(preset_path(name)?, keymap)
+    }
+    pub fn load_preset(name: &str) -> anyhow::Result<Keymap> {
+        read_preset_file(preset_path(name)?)
+    }
+    // Named after the file,returns the preset name
+    pub fn import_preset(path: impl AsRef<Path>) -> anyhow::Result<String> {
+        let path = path.as_ref();
+        let keymap = read_preset_file(path)?;
+        let name = path
+            .file_stem()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        save_preset(&name, &keymap)?;
+        Ok(name)
+    }
+    // Replaces the keymap of a game's saved config,leaving everything else alone
+    pub fn apply_to_game(keymap: &Keymap, title: &GameTitle) -> anyhow::Result<()> {
+        let mut config = LindberghConfig::default();
+        config.read_from_lindbergh_conf_by_title(title)?;
+        config.input_method = keymap.clone();
+        config.write_to_lindbergh_conf(title)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_preset_file() {
+            let path =
+                std::env::temp_dir().join(format!("loader-gui-test-{}.preset", std::process::id()));
+            let mut keymap = Keymap::default().into_sdl();
+            keymap.get_sdl_mut().unwrap().player2.start = egui::Key::F5.into();
+            create_preset_file(&path, &keymap).unwrap();
+            assert!(read_preset_file(&path).unwrap() == keymap);
+            // A preset of the same name is left alone
+            assert!(create_preset_file(&path, &Keymap::default()).is_err());
+            assert!(read_preset_file(&path).unwrap() == keymap);
+            fs::remove_file(&path).unwrap();
+            assert!(preset_path("../evil").is_err());
+            assert!(preset_path("Cabinet 1").is_ok());
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use anyhow::anyhow;
+use std::fs;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub const DISCOVERY_PORT: u16 = 47430;
+const MAGIC: &str = "LOADER_GUI_ANNOUNCE 1";
+// Launchers send these back as they are,so cabinets can tell their peers are reachable
+const ECHO_MAGIC: &str = "LOADER_GUI_ECHO 1";
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(2);
+// Peers that stopped announcing are dropped after this
+const PEER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What every launcher tells the others about itself
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Announcement {
+    // Random per launcher,so it can skip its own broadcasts
+    pub id: u64,
+    pub name: String,
+    pub ip: Ipv4Addr,
+    // `GameTitle` as written in config file names
+    pub game: String,
+    // Counting from 1
+    pub seat: Option<usize>,
+}
+impl Announcement {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut text = format!(
+            "{}\nID {}\nNAME {}\nIP {}\nGAME {}\n",
+            MAGIC, self.id, self.name, self.ip, self.game
+        );
+        if let Some(seat) = self.seat {
+            text += &format!("SEAT {}\n", seat);
+        }
+        text.into_bytes()
+    }
+    pub fn parse(buf: &[u8]) -> anyhow::Result<Self> {
+        let text = std::str::from_utf8(buf)?;
+        let mut lines = text.lines();
+        if lines.next() != Some(MAGIC) {
+            return Err(anyhow!("Not a launcher announcement"));
+        }
+        let mut id = None;
+        let mut name = String::new();
+        let mut ip = None;
+        let mut game = String::new();
+        let mut seat = None;
+        for i in lines {
+            let Some((key, value)) = i.split_once(' ') else {
+                continue;
+            };
+            match key {
+                "ID" => id = Some(value.parse()?),
+                "NAME" => name = value.to_string(),
+                "IP" => ip = Some(value.parse()?),
+                "GAME" => game = value.to_string(),
+                "SEAT" => seat = Some(value.parse()?),
+                // Newer launchers may send more
+                _ => {}
+            }
+        }
+        Ok(Self {
+            id: id.ok_or(anyhow!("Announcement without ID"))?,
+            name,
+            ip: ip.ok_or(anyhow!("Announcement without IP"))?,
+            game,
+            seat,
+        })
+    }
+}
+
+pub fn machine_name() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|x| x.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn random_id() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_nanos() as u64)
+        .unwrap_or_default();
+    nanos ^ ((std::process::id() as u64) << 32)
+}
+
+// Peers running the same game,with this machine,in seat order
+// Peers that don't announce a seat go after the ones that do,by IP
+pub fn seat_order(me: &Announcement, peers: &[Announcement]) -> Vec<Ipv4Addr> {
+    let mut list: Vec<&Announcement> = peers
+        .iter()
+        .filter(|x| x.game == me.game && x.id != me.id)
+        .chain([me])
+        .collect();
+    list.sort_by_key(|x| (x.seat.unwrap_or(usize::MAX), x.ip));
+    list.iter().map(|x| x.ip).collect()
+}
+
+// True when the launcher at `addr` answers within `timeout`
+pub fn echo(addr: SocketAddr, timeout: Duration) -> bool {
+    let Ok(socket) = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)) else {
+        return false;
+    };
+    let token = format!("{} {}", ECHO_MAGIC, random_id());
+    if socket.send_to(token.as_bytes(), addr).is_err() {
+        return false;
+    }
+    let start = Instant::now();
+    let mut buf = [0u8; 64];
+    while let Some(left) = timeout.checked_sub(start.elapsed()) {
+        if left.is_zero() || socket.set_read_timeout(Some(left)).is_err() {
+            return false;
+        }
+        match socket.recv_from(&mut buf) {
+            Ok((n, _)) if buf[..n] == *token.as_bytes() => return true,
+            Ok(_) => {}
+            Err(_) => return false,
+        }
+    }
+    false
+}
+
+struct Shared {
+    me: Announcement,
+    targets: Vec<SocketAddr>,
+    // Changed since the last announcement
+    changed: bool,
+}
+
+/// Announces this launcher on the LAN and collects the announcements of the others
+pub struct Discovery {
+    pub id: u64,
+    pub local_addr: SocketAddr,
+    shared: Arc<Mutex<Shared>>,
+    receiver: Receiver<Announcement>,
+    peers: Vec<(Announcement, Instant)>,
+}
+impl Discovery {
+    // Binds 0.0.0.0:DISCOVERY_PORT,tests bind loopback ports instead
+    pub fn start(bind: SocketAddr) -> anyhow::Result<Self> {
+        let socket =
+            UdpSocket::bind(bind).map_err(|e| anyhow!("Unable to listen on {}: {}", bind, e))?;
+        socket.set_broadcast(true)?;
+        let local_addr = socket.local_addr()?;
+        socket.set_read_timeout(Some(Duration::from_millis(200)))?;
+        let id = random_id();
+        let shared = Arc::new(Mutex::new(Shared {
+            me: Announcement {
+                id,
+                name: machine_name(),
+                ip: Ipv4Addr::UNSPECIFIED,
+                game: String::new(),
+                seat: None,
+            },
+            targets: vec![],
+            changed: false,
+        }));
+        let weak = Arc::downgrade(&shared);
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buf = [0u8; 1024];
+            let mut last_announce: Option<Instant> = None;
+            // Stops once the Discovery is dropped
+            while let Some(shared) = weak.upgrade() {
+                let mut locked = shared.lock().unwrap();
+                if locked.changed || last_announce.is_none_or(|x| x.elapsed() >= ANNOUNCE_INTERVAL)
+                {
+                    let packet = locked.me.to_bytes();
+                    for i in &locked.targets {
+                        let _ = socket.send_to(&packet, i);
+                    }
+                    locked.changed = false;
+                    last_announce = Some(Instant::now());
+                }
+                drop(locked);
+                drop(shared);
+                let Ok((n, from)) = socket.recv_from(&mut buf) else {
+                    continue;
+                };
+                if buf[..n].starts_with(ECHO_MAGIC.as_bytes()) {
+                    let _ = socket.send_to(&buf[..n], from);
+                    continue;
+                }
+                if let Ok(x) = Announcement::parse(&buf[..n])
+                    && x.id != id
+                    && sender.send(x).is_err()
+                {
+                    return;
+                }
+            }
+        });
+        Ok(Self {
+            id,
+            local_addr,
+            shared,
+            receiver,
+            peers: vec![],
+        })
+    }
+    pub fn start_lan() -> anyhow::Result<Self> {
+        Self::start(SocketAddr::from((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT)))
+    }
+    // What to announce from now on,and where to
+    pub fn announce(
+        &self,
+        ip: Ipv4Addr,
+        game: &str,
+        seat: Option<usize>,
+        targets: Vec<SocketAddr>,
+    ) {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.me.ip == ip
+            && shared.me.game == game
+            && shared.me.seat == seat
+            && shared.targets == targets
+        {
+            return;
+        }
+        shared.me.ip = ip;
+        shared.me.game = game.to_string();
+        shared.me.seat = seat;
+        shared.targets = targets;
+        shared.changed = true;
+    }
+    pub fn me(&self) -> Announcement {
+        self.shared.lock().unwrap().me.clone()
+    }
+    pub fn update(&mut self) {
+        for i in self.receiver.try_iter() {
+            self.peers.retain(|x| x.0.id != i.id);
+            self.peers.push((i, Instant::now()));
+        }
+        self.peers.retain(|x| x.1.elapsed() < PEER_TIMEOUT);
+        self.peers.sort_by_key(|x| x.0.ip);
+    }
+    pub fn peers(&self) -> Vec<Announcement> {
+        self.peers.iter().map(|x| x.0.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loopback_discovery() {
+        let me = Announcement {
+            id: 7,
+            name: "cab1".into(),
+            ip: Ipv4Addr::new(192, 168, 1, 2),
+            game: "InitialD_4".into(),
+            seat: Some(2),
+        };
+        assert_eq!(Announcement::parse(&me.to_bytes()).unwrap(), me);
+        assert!(Announcement::parse(b"hello").is_err());
+
+        let loopback = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+        let mut instances: Vec<Discovery> = (0..4)
+            .map(|_| Discovery::start(loopback).unwrap())
+            .collect();
+        // Loopback has no broadcast,every instance announces to all of them
+        let targets: Vec<SocketAddr> = instances.iter().map(|x| x.local_addr).collect();
+        for (cnt, i) in instances.iter().enumerate() {
+            let game = if cnt == 3 {
+                "Outrun_2_SP_SDX"
+            } else {
+                "InitialD_4"
+            };
+            // The first instance doesn't know its seat yet
+            let seat = (cnt > 0).then_some(3 - cnt);
+            i.announce(
+                Ipv4Addr::new(10, 0, 0, cnt as u8 + 1),
+                game,
+                seat,
+                targets.clone(),
+            );
+        }
+        let start = Instant::now();
+        while instances.iter().any(|x| x.peers().len() < 3) {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+            instances.iter_mut().for_each(|x| x.update());
+        }
+        assert!(instances[0].peers().iter().all(|x| x.id != instances[0].id));
+        assert_eq!(
+            seat_order(&instances[0].me(), &instances[0].peers()),
+            vec![
+                Ipv4Addr::new(10, 0, 0, 3),
+                Ipv4Addr::new(10, 0, 0, 2),
+                Ipv4Addr::new(10, 0, 0, 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use anyhow::anyhow;
+use std::env;
+use std::process::Command;
+
+/// A resolution and refresh rate an output can run at
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DisplayMode {
+    pub width: u32,
+    pub height: u32,
+    pub rate: f64,
+}
+impl DisplayMode {
+    // WIDTHxHEIGHT@RATE,as written by Display
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let error = || anyhow!("\"{}\" is not WIDTHxHEIGHT@RATE", s);
+        let (size, rate) = s.split_once('@').ok_or_else(error)?;
+        let (width, height) = size.split_once('x').ok_or_else(error)?;
+        Ok(Self {
+            width: width.parse()?,
+            height: height.parse()?,
+            rate: rate.parse()?,
+        })
+    }
+}
+impl std::fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}@{:.2}", self.width, self.height, self.rate)
+    }
+}
+
+/// A monitor that is connected and switched on
+#[derive(Debug, PartialEq, Clone)]
+pub struct Output {
+    pub name: String,
+    pub primary: bool,
+    // Top left corner on the X screen
+    pub position: (i32, i32),
+    pub current: Option<DisplayMode>,
+    // The monitor's native mode
+    pub preferred: Option<DisplayMode>,
+    pub modes: Vec<DisplayMode>,
+}
+
+// The X display the game opens its window on
+pub fn x_display() -> String {
+    env::var("DISPLAY").unwrap_or(":0".into())
+}
+
+pub fn parse_xrandr(text: &str) -> Vec<Output> {
+    let mut outputs: Vec<Output> = Vec::new();
+    // Modes below a disconnected or switched off output are skipped
+    let mut active = false;
+    for line in text.lines() {
+        if line.starts_with(' ') {
+            let Some(output) = outputs.last_mut().filter(|_| active) else {
+                continue;
+            };
+            let mut r = line.split_whitespace();
+            let Some((width, height)) = r.next().and_then(|x| x.split_once('x')) else {
+                continue;
+            };
+            // Interlaced modes end with "i" and are left out
+            let (Ok(width), Ok(height)) = (width.parse(), height.parse()) else {
+                continue;
+            };
+            for i in r {
+                // "60.00 +" is printed when the preferred mode isn't the current one
+                if i == "+" {
+                    output.preferred = output.modes.last().copied();
+                    continue;
+                }
+                let Ok(rate) = i.trim_end_matches(['*', '+']).parse() else {
+                    continue;
+                };
+                let mode = DisplayMode {
+                    width,
+                    height,
+                    rate,
+                };
+                if i.contains('*') {
+                    output.current = Some(mode);
+                }
+                if i.contains('+') {
+                    output.preferred = Some(mode);
+                }
+                output.modes.push(mode);
+            }
+            continue;
+        }
+        active = false;
+        let r = line.split_whitespace().collect::<Vec<&str>>();
+        if r.len() < 3 || r[1] != "connected" {
+            continue;
+        }
+        let primary = r[2] == "primary";
+        // WIDTHxHEIGHT+X+Y,missing when the output is switched off
+        let geometry = r
+            .get(if primary { 3 } else { 2 })
+            .map(|x| x.split('+').collect::<Vec<&str>>())
+            .unwrap_or_default();
+        let (Some(x), Some(y)) = (
+            geometry.get(1).and_then(|x| x.parse().ok()),
+            geometry.get(2).and_then(|x| x.parse().ok()),
+        ) else {
+            continue;
+        };
+        outputs.push(Output {
+            name: r[0].to_string(),
+            primary,
+            position: (x, y),
+            current: None,
+            preferred: None,
+            modes: vec![],
+        });
+        active = true;
+    }
+    outputs
+}
+
+pub fn list_outputs() -> anyhow::Result<Vec<Output>> {
+    let out = Command::new("xrandr")
+        .arg("--query")
+        .env("DISPLAY", x_display())
+        .output()
+        .map_err(|e| anyhow!("Unable to run xrandr: {}", e))?;
+    if !out.status.success() {
+        return Err(anyhow!(
+            "xrandr failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    Ok(parse_xrandr(&String::from_utf8_lossy(&out.stdout)))
+}
+
+pub fn set_mode(output: &str, mode: DisplayMode) -> anyhow::Result<()> {
+    let status = Command::new("xrandr")
+        .env("DISPLAY", x_display())
+        .args([
+            "--output",
+            output,
+            "--mode",
+            &format!("{}x{}", mode.width, mode.height),
+            "--rate",
+            &format!("{:.2}", mode.rate),
+        ])
+        .status()
+        .map_err(|e| anyhow!("Unable to run xrandr: {}", e))?;
+    if !status.success() {
+        return Err(anyhow!("xrandr couldn't switch {} to {}", output, mode));
+    }
+    Ok(())
+}
+
+// SDL numbers the displays with the primary one first,then in xrandr order
+pub fn sdl_index(outputs: &[Output], name: &str) -> Option<usize> {
+    let mut sorted = outputs.iter().collect::<Vec<&Output>>();
+    sorted.sort_by_key(|x| !x.primary);
+    sorted.iter().position(|x| x.name == name)
+}
+
+/// Where the game is shown,kept in the header comments of the game's config
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DisplayTarget {
+    // Empty for wherever the game opens by itself
+    pub output: String,
+    // None keeps the output's current mode
+    pub mode: Option<DisplayMode>,
+}
+impl DisplayTarget {
+    // Environment for the game's process
+    pub fn env(&self, outputs: &[Output]) -> Vec<(String, String)> {
+        let mut env = vec![("DISPLAY".to_string(), x_display())];
+        let Some(output) = outputs.iter().find(|x| x.name == self.output) else {
+            return env;
+        };
+        if let Some(index) = sdl_index(outputs, &output.name) {
+            env.push((
+                "SDL_VIDEO_FULLSCREEN_DISPLAY".to_string(),
+                index.to_string(),
+            ));
+            env.push(("SDL_VIDEO_FULLSCREEN_HEAD".to_string(), index.to_string()));
+        }
+        // Windowed games open on the output too
+        env.push((
+            "SDL_VIDEO_WINDOW_POS".to_string(),
+            format!("{},{}", output.position.0, output.position.1),
+        ));
+        env
+    }
+    // Switches the output to the chosen mode,returns the mode to switch back to after the game
+    pub fn apply(&self, outputs: &[Output]) -> anyhow::Result<Option<DisplayMode>> {
+        let Some(mode) = self.mode else {
+            return Ok(None);
+        };
+        let output = outputs
+            .iter()
+            .find(|x| x.name == self.output)
+            .ok_or(anyhow!("Display {} is not connected", self.output))?;
+        if output.current == Some(mode) {
+            return Ok(None);
+        }
+        if !output.modes.contains(&mode) {
+            return Err(anyhow!("{} doesn't support {}", output.name, mode));
+        }
+        set_mode(&output.name, mode)?;
+        Ok(output.current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xrandr() {
+        let text = "Screen 0: minimum 320 x 200, current 3200 x 1080, maximum 16384 x 16384
+DP-1 connected 1280x1024+1920+0 (normal left inverted right x axis y axis) 376mm x 301mm
+   1280x1024     60.02 +  75.02*
+   1024x768      60.00
+HDMI-1 connected primary 1920x1080+0+0 (normal left inverted right x axis y axis) 527mm x 296mm
+   1920x1080     60.00*+  50.00    59.94
+   1920x1080i    60.00    50.00
+VGA-1 disconnected (normal left inverted right x axis y axis)
+   640x480       59.94
+HDMI-2 connected (normal left inverted right x axis y axis)
+   1024x768      60.00 +
+";
+        let outputs = parse_xrandr(text);
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].position, (1920, 0));
+        assert_eq!(outputs[0].modes.len(), 3);
+        assert_eq!(outputs[0].current.unwrap().to_string(), "1280x1024@75.02");
+        assert_eq!(outputs[0].preferred, Some(outputs[0].modes[0]));
+        assert!(outputs[1].primary);
+        assert_eq!(outputs[1].modes.len(), 3);
+        assert_eq!(sdl_index(&outputs, "HDMI-1"), Some(0));
+        assert_eq!(sdl_index(&outputs, "DP-1"), Some(1));
+
+        let mode = DisplayMode::parse("1024x768@60.00").unwrap();
+        assert_eq!(mode, outputs[0].modes[2]);
+        assert!(DisplayMode::parse("1024x768").is_err());
+        let target = DisplayTarget {
+            output: "DP-1".into(),
+            mode: Some(mode),
+        };
+        let env = target.env(&outputs);
+        assert!(env.contains(&("SDL_VIDEO_FULLSCREEN_DISPLAY".into(), "1".into())));
+        assert!(env.contains(&("SDL_VIDEO_WINDOW_POS".into(), "1920,0".into())));
+        let target = DisplayTarget {
+            output: "DP-1".into(),
+            mode: Some(DisplayMode::parse("800x600@60").unwrap()),
+        };
+        assert!(target.apply(&outputs).is_err());
+    }
+}
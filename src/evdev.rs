@@ -0,0 +1,684 @@
+use anyhow::anyhow;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read};
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+pub const EV_SYN: u16 = 0;
+pub const EV_KEY: u16 = 1;
+pub const EV_REL: u16 = 2;
+pub const EV_ABS: u16 = 3;
+
+// How far an axis has to travel before it's picked up when binding by moving it
+pub const AXIS_CAPTURE_THRESHOLD: i32 = 64;
+
+// Lowercase fragments of the names common lightguns report
+const LIGHTGUN_NAMES: [&str; 7] = [
+    "sinden", "gun4ir", "aimtrak", "ultimarc", "samco", "blamcon", "lightgun",
+];
+
+// struct input_event { struct timeval time; __u16 type; __u16 code; __s32 value; }
+// The size of the timeval depends on the target's time_t,not only its pointer width
+pub const EVENT_SIZE: usize = size_of::<libc::input_event>();
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct InputEvent {
+    pub kind: u16,
+    pub code: u16,
+    pub value: i32,
+}
+impl InputEvent {
+    pub fn from_bytes(buf: &[u8]) -> Option<Self> {
+        if buf.len() < EVENT_SIZE {
+            return None;
+        }
+        let buf = &buf[EVENT_SIZE - 8..EVENT_SIZE];
+        Some(Self {
+            kind: u16::from_ne_bytes([buf[0], buf[1]]),
+            code: u16::from_ne_bytes([buf[2], buf[3]]),
+            value: i32::from_ne_bytes([buf[4], buf[5], buf[6], buf[7]]),
+        })
+    }
+    pub fn is_press(&self) -> bool {
+        self.kind == EV_KEY && self.value == 1
+    }
+}
+pub fn parse_events(buf: &[u8]) -> Vec<InputEvent> {
+    buf.chunks_exact(EVENT_SIZE)
+        .filter_map(InputEvent::from_bytes)
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InputDevice {
+    pub path: PathBuf,
+    pub name: String,
+}
+impl InputDevice {
+    // lindbergh-loader names devices like "AT_TRANSLATED_SET_2_KEYBOARD"
+    pub fn loader_name(&self) -> String {
+        self.name
+            .trim()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+    // Sinden,GUN4IR,AimTrak and friends
+    pub fn is_lightgun(&self) -> bool {
+        let name = self.name.to_ascii_lowercase();
+        LIGHTGUN_NAMES.iter().any(|x| name.contains(x))
+    }
+    // e.g. "AT_TRANSLATED_SET_2_KEYBOARD_KEY_Q"
+    pub fn binding_name(&self, event: &InputEvent) -> Option<String> {
+        Some(format!(
+            "{}_{}",
+            self.loader_name(),
+            code_name(event.kind, event.code)?
+        ))
+    }
+}
+
+pub fn list_devices() -> anyhow::Result<Vec<InputDevice>> {
+    let mut devices = Vec::new();
+    for i in fs::read_dir("/sys/class/input")? {
+        let i = i?;
+        let node = i.file_name().to_string_lossy().to_string();
+        if !node.starts_with("event") {
+            continue;
+        }
+        let name = fs::read_to_string(i.path().join("device/name")).unwrap_or_default();
+        devices.push(InputDevice {
+            path: PathBuf::from(format!("/dev/input/{}", node)),
+            name: name.trim().to_string(),
+        });
+    }
+    devices.sort_by_key(|x| {
+        x.path
+            .to_string_lossy()
+            .trim_start_matches("/dev/input/event")
+            .parse::<u32>()
+            .unwrap_or(u32::MAX)
+    });
+    Ok(devices)
+}
+
+// How long a read waits before checking whether the capture is still wanted
+const READ_TIMEOUT_MS: i32 = 100;
+
+// An input device whose reads time out,so the reading thread never outlives its capture
+struct TimedReader(File);
+impl Read for TimedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut fd = libc::pollfd {
+            fd: self.0.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: fd points at a single pollfd that lives for the whole call
+        match unsafe { libc::poll(&mut fd, 1, READ_TIMEOUT_MS) } {
+            0 => Err(ErrorKind::TimedOut.into()),
+            x if x < 0 => Err(io::Error::last_os_error()),
+            _ => self.0.read(buf),
+        }
+    }
+}
+
+/// Reads events from a device on another thread,so the UI can poll them every frame
+pub struct EventCapture {
+    pub device: InputDevice,
+    receiver: Receiver<InputEvent>,
+    // Cleared when reading fails,e.g. the device was unplugged
+    open: Arc<AtomicBool>,
+    // Set when the capture is dropped,the thread stops at its next read
+    stopped: Arc<AtomicBool>,
+}
+impl Drop for EventCapture {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+impl EventCapture {
+    pub fn start(device: &InputDevice) -> anyhow::Result<Self> {
+        let f = File::open(&device.path).map_err(|e| {
+            anyhow!(
+                "Unable to open {}: {}\nMake sure your user is in the \"input\" group",
+                device.path.display(),
+                e
+            )
+        })?;
+        Ok(Self::from_reader(TimedReader(f), device))
+    }
+    pub fn from_reader(mut reader: impl Read + Send + 'static, device: &InputDevice) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let open = Arc::new(AtomicBool::new(true));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (reading, stop) = (open.clone(), stopped.clone());
+        thread::spawn(move || {
+            let mut buf = [0u8; EVENT_SIZE * 64];
+            while !stop.load(Ordering::Relaxed) {
+                let n = match reader.read(&mut buf) {
+                    Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted) => {
+                        continue;
+                    }
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                for i in parse_events(&buf[..n]) {
+                    // The capture has been dropped
+                    if sender.send(i).is_err() {
+                        return;
+                    }
+                }
+            }
+            reading.store(false, Ordering::Relaxed);
+        });
+        Self {
+            device: device.clone(),
+            receiver,
+            open,
+            stopped,
+        }
+    }
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::Relaxed)
+    }
+    pub fn poll(&self) -> Vec<InputEvent> {
+        self.receiver.try_iter().collect()
+    }
+    // The binding of the first key/button pressed since the last poll
+    pub fn poll_binding(&self) -> Option<String> {
+        self.poll()
+            .iter()
+            .filter(|x| x.is_press())
+            .find_map(|x| self.device.binding_name(x))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct AxisState {
+    pub value: i32,
+    // Range seen since the readout was cleared
+    pub min: i32,
+    pub max: i32,
+}
+
+/// Live values of every absolute axis seen on a device,used for calibration
+#[derive(Default)]
+pub struct AxisReadout {
+    axes: HashMap<String, AxisState>,
+}
+impl AxisReadout {
+    pub fn update(&mut self, device: &InputDevice, events: &[InputEvent]) {
+        for i in events.iter().filter(|x| x.kind == EV_ABS) {
+            let Some(name) = device.binding_name(i) else {
+                continue;
+            };
+            self.axes
+                .entry(name)
+                .and_modify(|x| {
+                    x.value = i.value;
+                    x.min = x.min.min(i.value);
+                    x.max = x.max.max(i.value);
+                })
+                .or_insert(AxisState {
+                    value: i.value,
+                    min: i.value,
+                    max: i.value,
+                });
+        }
+    }
+    pub fn get(&self, binding: &str) -> Option<AxisState> {
+        self.axes.get(binding).copied()
+    }
+    // The axis that travelled the furthest,if it went past the threshold
+    pub fn most_moved(&self, threshold: i32) -> Option<String> {
+        self.axes
+            .iter()
+            .filter(|x| x.1.max - x.1.min >= threshold)
+            .max_by_key(|x| x.1.max - x.1.min)
+            .map(|x| x.0.clone())
+    }
+    pub fn clear(&mut self) {
+        self.axes.clear();
+    }
+}
+
+/// Follows every device used by a set of bindings,to see which of them are active
+pub struct InputMonitor {
+    captures: Vec<EventCapture>,
+    held: HashSet<String>,
+    pub axes: AxisReadout,
+    // Why a device couldn't be opened,the others are still tested
+    pub errors: Vec<String>,
+}
+impl InputMonitor {
+    // Only devices that some binding refers to are opened
+    pub fn start(devices: &[InputDevice], bindings: &[String]) -> Self {
+        let mut captures = Vec::new();
+        let mut errors = Vec::new();
+        for i in devices {
+            let prefix = format!("{}_", i.loader_name());
+            if bindings.iter().any(|x| x.starts_with(&prefix)) {
+                match EventCapture::start(i) {
+                    Ok(capture) => captures.push(capture),
+                    Err(e) => errors.push(format!("{}: {}", i.name, e)),
+                }
+            }
+        }
+        let mut monitor = Self::from_captures(captures);
+        monitor.errors = errors;
+        monitor
+    }
+    pub fn from_captures(captures: Vec<EventCapture>) -> Self {
+        Self {
+            captures,
+            held: HashSet::new(),
+            axes: AxisReadout::default(),
+            errors: vec![],
+        }
+    }
+    pub fn update(&mut self) {
+        for capture in &self.captures {
+            let events = capture.poll();
+            for i in events.iter().filter(|x| x.kind == EV_KEY) {
+                let Some(name) = capture.device.binding_name(i) else {
+                    continue;
+                };
+                // 2 is autorepeat
+                if i.value == 0 {
+                    self.held.remove(&name);
+                } else {
+                    self.held.insert(name);
+                }
+            }
+            self.axes.update(&capture.device, &events);
+        }
+    }
+    pub fn is_held(&self, binding: &str) -> bool {
+        self.held.contains(binding)
+    }
+}
+
+pub fn code_name(kind: u16, code: u16) -> Option<&'static str> {
+    match kind {
+        EV_KEY => key_name(code),
+        EV_ABS => abs_name(code),
+        EV_REL => rel_name(code),
+        _ => None,
+    }
+}
+
+fn key_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        1 => "KEY_ESC",
+        2 => "KEY_1",
+        3 => "KEY_2",
+        4 => "KEY_3",
+        5 => "KEY_4",
+        6 => "KEY_5",
+        7 => "KEY_6",
+        8 => "KEY_7",
+        9 => "KEY_8",
+        10 => "KEY_9",
+        11 => "KEY_0",
+        12 => "KEY_MINUS",
+        13 => "KEY_EQUAL",
+        14 => "KEY_BACKSPACE",
+        15 => "KEY_TAB",
+        16 => "KEY_Q",
+        17 => "KEY_W",
+        18 => "KEY_E",
+        19 => "KEY_R",
+        20 => "KEY_T",
+        21 => "KEY_Y",
+        22 => "KEY_U",
+        23 => "KEY_I",
+        24 => "KEY_O",
+        25 => "KEY_P",
+        26 => "KEY_LEFTBRACE",
+        27 => "KEY_RIGHTBRACE",
+        28 => "KEY_ENTER",
+        29 => "KEY_LEFTCTRL",
+        30 => "KEY_A",
+        31 => "KEY_S",
+        32 => "KEY_D",
+        33 => "KEY_F",
+        34 => "KEY_G",
+        35 => "KEY_H",
+        36 => "KEY_J",
+        37 => "KEY_K",
+        38 => "KEY_L",
+        39 => "KEY_SEMICOLON",
+        40 => "KEY_APOSTROPHE",
+        41 => "KEY_GRAVE",
+        42 => "KEY_LEFTSHIFT",
+        43 => "KEY_BACKSLASH",
+        44 => "KEY_Z",
+        45 => "KEY_X",
+        46 => "KEY_C",
+        47 => "KEY_V",
+        48 => "KEY_B",
+        49 => "KEY_N",
+        50 => "KEY_M",
+        51 => "KEY_COMMA",
+        52 => "KEY_DOT",
+        53 => "KEY_SLASH",
+        54 => "KEY_RIGHTSHIFT",
+        55 => "KEY_KPASTERISK",
+        56 => "KEY_LEFTALT",
+        57 => "KEY_SPACE",
+        58 => "KEY_CAPSLOCK",
+        59 => "KEY_F1",
+        60 => "KEY_F2",
+        61 => "KEY_F3",
+        62 => "KEY_F4",
+        63 => "KEY_F5",
+        64 => "KEY_F6",
+        65 => "KEY_F7",
+        66 => "KEY_F8",
+        67 => "KEY_F9",
+        68 => "KEY_F10",
+        69 => "KEY_NUMLOCK",
+        70 => "KEY_SCROLLLOCK",
+        71 => "KEY_KP7",
+        72 => "KEY_KP8",
+        73 => "KEY_KP9",
+        74 => "KEY_KPMINUS",
+        75 => "KEY_KP4",
+        76 => "KEY_KP5",
+        77 => "KEY_KP6",
+        78 => "KEY_KPPLUS",
+        79 => "KEY_KP1",
+        80 => "KEY_KP2",
+        81 => "KEY_KP3",
+        82 => "KEY_KP0",
+        83 => "KEY_KPDOT",
+        87 => "KEY_F11",
+        88 => "KEY_F12",
+        96 => "KEY_KPENTER",
+        97 => "KEY_RIGHTCTRL",
+        98 => "KEY_KPSLASH",
+        99 => "KEY_SYSRQ",
+        100 => "KEY_RIGHTALT",
+        102 => "KEY_HOME",
+        103 => "KEY_UP",
+        104 => "KEY_PAGEUP",
+        105 => "KEY_LEFT",
+        106 => "KEY_RIGHT",
+        107 => "KEY_END",
+        108 => "KEY_DOWN",
+        109 => "KEY_PAGEDOWN",
+        110 => "KEY_INSERT",
+        111 => "KEY_DELETE",
+        119 => "KEY_PAUSE",
+        125 => "KEY_LEFTMETA",
+        126 => "KEY_RIGHTMETA",
+        127 => "KEY_COMPOSE",
+        0x100 => "BTN_0",
+        0x101 => "BTN_1",
+        0x102 => "BTN_2",
+        0x103 => "BTN_3",
+        0x104 => "BTN_4",
+        0x105 => "BTN_5",
+        0x106 => "BTN_6",
+        0x107 => "BTN_7",
+        0x108 => "BTN_8",
+        0x109 => "BTN_9",
+        0x110 => "BTN_LEFT",
+        0x111 => "BTN_RIGHT",
+        0x112 => "BTN_MIDDLE",
+        0x113 => "BTN_SIDE",
+        0x114 => "BTN_EXTRA",
+        0x115 => "BTN_FORWARD",
+        0x116 => "BTN_BACK",
+        0x117 => "BTN_TASK",
+        0x120 => "BTN_TRIGGER",
+        0x121 => "BTN_THUMB",
+        0x122 => "BTN_THUMB2",
+        0x123 => "BTN_TOP",
+        0x124 => "BTN_TOP2",
+        0x125 => "BTN_PINKIE",
+        0x126 => "BTN_BASE",
+        0x127 => "BTN_BASE2",
+        0x128 => "BTN_BASE3",
+        0x129 => "BTN_BASE4",
+        0x12a => "BTN_BASE5",
+        0x12b => "BTN_BASE6",
+        0x12f => "BTN_DEAD",
+        0x130 => "BTN_SOUTH",
+        0x131 => "BTN_EAST",
+        0x132 => "BTN_C",
+        0x133 => "BTN_NORTH",
+        0x134 => "BTN_WEST",
+        0x135 => "BTN_Z",
+        0x136 => "BTN_TL",
+        0x137 => "BTN_TR",
+        0x138 => "BTN_TL2",
+        0x139 => "BTN_TR2",
+        0x13a => "BTN_SELECT",
+        0x13b => "BTN_START",
+        0x13c => "BTN_MODE",
+        0x13d => "BTN_THUMBL",
+        0x13e => "BTN_THUMBR",
+        0x220 => "BTN_DPAD_UP",
+        0x221 => "BTN_DPAD_DOWN",
+        0x222 => "BTN_DPAD_LEFT",
+        0x223 => "BTN_DPAD_RIGHT",
+        _ => return None,
+    })
+}
+
+fn abs_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        0x00 => "ABS_X",
+        0x01 => "ABS_Y",
+        0x02 => "ABS_Z",
+        0x03 => "ABS_RX",
+        0x04 => "ABS_RY",
+        0x05 => "ABS_RZ",
+        0x06 => "ABS_THROTTLE",
+        0x07 => "ABS_RUDDER",
+        0x08 => "ABS_WHEEL",
+        0x09 => "ABS_GAS",
+        0x0a => "ABS_BRAKE",
+        0x10 => "ABS_HAT0X",
+        0x11 => "ABS_HAT0Y",
+        0x12 => "ABS_HAT1X",
+        0x13 => "ABS_HAT1Y",
+        0x14 => "ABS_HAT2X",
+        0x15 => "ABS_HAT2Y",
+        0x16 => "ABS_HAT3X",
+        0x17 => "ABS_HAT3Y",
+        0x18 => "ABS_PRESSURE",
+        0x19 => "ABS_DISTANCE",
+        0x1a => "ABS_TILT_X",
+        0x1b => "ABS_TILT_Y",
+        0x1c => "ABS_TOOL_WIDTH",
+        0x20 => "ABS_VOLUME",
+        0x28 => "ABS_MISC",
+        _ => return None,
+    })
+}
+
+fn rel_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        0x00 => "REL_X",
+        0x01 => "REL_Y",
+        0x02 => "REL_Z",
+        0x06 => "REL_HWHEEL",
+        0x08 => "REL_WHEEL",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    fn event_bytes(kind: u16, code: u16, value: i32) -> Vec<u8> {
+        let mut buf = vec![0u8; EVENT_SIZE - 8];
+        buf.extend_from_slice(&kind.to_ne_bytes());
+        buf.extend_from_slice(&code.to_ne_bytes());
+        buf.extend_from_slice(&value.to_ne_bytes());
+        buf
+    }
+
+    #[test]
+    fn test_capture_binding() {
+        let device = InputDevice {
+            path: PathBuf::from("/dev/input/event3"),
+            name: "AT Translated Set 2 keyboard".into(),
+        };
+        let mut stream = Vec::new();
+        // release of a previous key,then Q pressed,then SYN
+        stream.extend(event_bytes(EV_KEY, 30, 0));
+        stream.extend(event_bytes(EV_KEY, 16, 1));
+        stream.extend(event_bytes(EV_SYN, 0, 0));
+        assert_eq!(parse_events(&stream).len(), 3);
+
+        let capture = EventCapture::from_reader(Cursor::new(stream), &device);
+        let mut binding = None;
+        for _ in 0..100 {
+            binding = capture.poll_binding();
+            if binding.is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(
+            binding.as_deref(),
+            Some("AT_TRANSLATED_SET_2_KEYBOARD_KEY_Q")
+        );
+        // The stream has ended,like an unplugged device
+        for _ in 0..100 {
+            if !capture.is_open() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!capture.is_open());
+
+        // Dropping a capture of a quiet device ends its thread,which closes the device
+        let (reader, mut writer) = io::pipe().unwrap();
+        let capture = EventCapture::from_reader(
+            TimedReader(File::from(std::os::fd::OwnedFd::from(reader))),
+            &device,
+        );
+        drop(capture);
+        let mut closed = false;
+        for _ in 0..100 {
+            thread::sleep(Duration::from_millis(10));
+            if std::io::Write::write_all(&mut writer, &[0]).is_err() {
+                closed = true;
+                break;
+            }
+        }
+        assert!(closed);
+        let touchpad = InputDevice {
+            path: PathBuf::from("/dev/input/event5"),
+            name: "SynPS/2 Synaptics TouchPad".into(),
+        };
+        assert_eq!(
+            touchpad.binding_name(&InputEvent {
+                kind: EV_ABS,
+                code: 0,
+                value: 100
+            }),
+            Some("SYNPS_2_SYNAPTICS_TOUCHPAD_ABS_X".into())
+        );
+        assert!(!touchpad.is_lightgun());
+        assert!(
+            InputDevice {
+                path: PathBuf::from("/dev/input/event9"),
+                name: "Unknown SindenLightgun Mouse".into(),
+            }
+            .is_lightgun()
+        );
+    }
+
+    #[test]
+    fn test_input_monitor() {
+        let device = InputDevice {
+            path: PathBuf::from("/dev/input/event3"),
+            name: "AT Translated Set 2 keyboard".into(),
+        };
+        let mut stream = Vec::new();
+        // Q held,W tapped
+        stream.extend(event_bytes(EV_KEY, 16, 1));
+        stream.extend(event_bytes(EV_KEY, 17, 1));
+        stream.extend(event_bytes(EV_KEY, 17, 0));
+        let mut monitor = InputMonitor::from_captures(vec![EventCapture::from_reader(
+            Cursor::new(stream),
+            &device,
+        )]);
+        for _ in 0..100 {
+            monitor.update();
+            if monitor.is_held("AT_TRANSLATED_SET_2_KEYBOARD_KEY_Q") {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(monitor.is_held("AT_TRANSLATED_SET_2_KEYBOARD_KEY_Q"));
+        assert!(!monitor.is_held("AT_TRANSLATED_SET_2_KEYBOARD_KEY_W"));
+
+        // A device that can't be opened is reported,not fatal
+        let missing = InputDevice {
+            path: PathBuf::from("/nonexistent/event99"),
+            name: "Missing Pad".into(),
+        };
+        let monitor =
+            InputMonitor::start(&[missing, device], &["MISSING_PAD_BTN_SOUTH".to_string()]);
+        assert_eq!(monitor.errors.len(), 1);
+        assert!(monitor.errors[0].starts_with("Missing Pad: "));
+    }
+
+    #[test]
+    fn test_axis_readout() {
+        let wheel = InputDevice {
+            path: PathBuf::from("/dev/input/event7"),
+            name: "Logitech G29".into(),
+        };
+        let abs = |code, value| InputEvent {
+            kind: EV_ABS,
+            code,
+            value,
+        };
+        let mut readout = AxisReadout::default();
+        // a noisy pedal and a wheel turned all the way
+        readout.update(
+            &wheel,
+            &[
+                abs(2, 255),
+                abs(2, 250),
+                abs(0, 32768),
+                abs(0, 0),
+                abs(0, 40000),
+            ],
+        );
+        let steering = readout.get("LOGITECH_G29_ABS_X").unwrap();
+        assert_eq!(
+            (steering.value, steering.min, steering.max),
+            (40000, 0, 40000)
+        );
+        assert_eq!(
+            readout.most_moved(AXIS_CAPTURE_THRESHOLD).as_deref(),
+            Some("LOGITECH_G29_ABS_X")
+        );
+        readout.clear();
+        readout.update(&wheel, &[abs(2, 255), abs(2, 250)]);
+        assert!(readout.most_moved(AXIS_CAPTURE_THRESHOLD).is_none());
+    }
+}
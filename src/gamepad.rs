@@ -0,0 +1,151 @@
+use crate::evdev::{EV_ABS, EV_KEY, EventCapture, InputDevice, InputEvent};
+use std::fs;
+use std::time::{Duration, Instant};
+
+// How often /dev/input is looked at again for pads plugged in later
+pub const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What a gamepad asks the launcher to do
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NavAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Accept,
+    Back,
+}
+
+// Joysticks and gamepads also get a /dev/input/jsN node next to their event node
+pub fn is_gamepad(device: &InputDevice) -> bool {
+    let Some(node) = device.path.file_name() else {
+        return false;
+    };
+    let Ok(dir) = fs::read_dir(format!(
+        "/sys/class/input/{}/device",
+        node.to_string_lossy()
+    )) else {
+        return false;
+    };
+    dir.flatten()
+        .any(|x| x.file_name().to_string_lossy().starts_with("js"))
+}
+
+pub fn nav_action(event: &InputEvent) -> Option<NavAction> {
+    match (event.kind, event.code, event.value) {
+        // BTN_SOUTH,BTN_START
+        (EV_KEY, 0x130 | 0x13b, 1) => Some(NavAction::Accept),
+        // BTN_EAST,BTN_SELECT
+        (EV_KEY, 0x131 | 0x13a, 1) => Some(NavAction::Back),
+        (EV_KEY, 0x220, 1) => Some(NavAction::Up),
+        (EV_KEY, 0x221, 1) => Some(NavAction::Down),
+        (EV_KEY, 0x222, 1) => Some(NavAction::Left),
+        (EV_KEY, 0x223, 1) => Some(NavAction::Right),
+        // Most pads report the d-pad as ABS_HAT0X/ABS_HAT0Y
+        (EV_ABS, 0x10, -1) => Some(NavAction::Left),
+        (EV_ABS, 0x10, 1) => Some(NavAction::Right),
+        (EV_ABS, 0x11, -1) => Some(NavAction::Up),
+        (EV_ABS, 0x11, 1) => Some(NavAction::Down),
+        _ => None,
+    }
+}
+
+/// Listens to every gamepad so the launcher can be used from the couch
+pub struct GamepadNav {
+    captures: Vec<EventCapture>,
+    scanned: Instant,
+}
+impl GamepadNav {
+    pub fn start(devices: &[InputDevice]) -> Self {
+        let mut nav = Self::from_captures(vec![]);
+        nav.rescan(devices);
+        nav
+    }
+    pub fn from_captures(captures: Vec<EventCapture>) -> Self {
+        Self {
+            captures,
+            scanned: Instant::now(),
+        }
+    }
+    // Devices that can't be opened are skipped,the keyboard still works
+    pub fn rescan(&mut self, devices: &[InputDevice]) {
+        self.captures.retain(|x| x.is_open());
+        for i in devices.iter().filter(|x| is_gamepad(x)) {
+            if !self.captures.iter().any(|x| x.device.path == i.path)
+                && let Ok(capture) = EventCapture::start(i)
+            {
+                self.captures.push(capture);
+            }
+        }
+        self.scanned = Instant::now();
+    }
+    pub fn needs_rescan(&self) -> bool {
+        self.scanned.elapsed() >= RESCAN_INTERVAL
+    }
+    pub fn is_empty(&self) -> bool {
+        self.captures.is_empty()
+    }
+    pub fn poll(&self) -> Vec<NavAction> {
+        self.captures
+            .iter()
+            .flat_map(|x| x.poll())
+            .filter_map(|x| nav_action(&x))
+            .collect()
+    }
+    // Throws away what was pressed while the launcher wasn't looking,e.g. during a game
+    pub fn drain(&self) {
+        for i in &self.captures {
+            i.poll();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evdev::EVENT_SIZE;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+    use std::thread;
+
+    #[test]
+    fn test_nav_action() {
+        let event = |kind, code, value| InputEvent { kind, code, value };
+        assert_eq!(
+            nav_action(&event(EV_KEY, 0x130, 1)),
+            Some(NavAction::Accept)
+        );
+        // releases and centring the hat do nothing
+        assert_eq!(nav_action(&event(EV_KEY, 0x130, 0)), None);
+        assert_eq!(nav_action(&event(EV_ABS, 0x11, 0)), None);
+        assert_eq!(nav_action(&event(EV_ABS, 0x11, -1)), Some(NavAction::Up));
+        assert_eq!(nav_action(&event(EV_ABS, 0x00, 1)), None);
+    }
+
+    #[test]
+    fn test_drain() {
+        let device = InputDevice {
+            path: PathBuf::from("/dev/input/event7"),
+            name: "Xbox Wireless Controller".into(),
+        };
+        // Enter then Escape,pressed while a game was running
+        let mut stream = Vec::new();
+        for code in [0x130u16, 0x131] {
+            stream.extend(vec![0u8; EVENT_SIZE - 8]);
+            stream.extend(EV_KEY.to_ne_bytes());
+            stream.extend(code.to_ne_bytes());
+            stream.extend(1i32.to_ne_bytes());
+        }
+        let capture = EventCapture::from_reader(Cursor::new(stream), &device);
+        // The whole stream has been queued once the capture sees its end
+        for _ in 0..100 {
+            if !capture.is_open() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let nav = GamepadNav::from_captures(vec![capture]);
+        nav.drain();
+        assert!(nav.poll().is_empty());
+    }
+}
@@ -522,6 +522,18 @@ impl GameTitle {
             GameTitle::Taisen_Mahjong_4_Evolution,
         ]
     }
+    // Games that save progress on magnetic cards
+    pub fn uses_card_reader(&self) -> bool {
+        matches!(
+            self,
+            Self::Virtua_Tennis_3
+                | Self::InitialD_4
+                | Self::InitalD_4_Export
+                | Self::InitialD_5_Japan
+                | Self::InitalD_5_Export_Ver_2
+                | Self::InitalD_5_Export_Ver_4
+        )
+    }
 }
 impl Display for GameTitle {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -0,0 +1,232 @@
+use crate::config::GpuType;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+pub const VENDOR_NVIDIA: u16 = 0x10de;
+pub const VENDOR_AMD: u16 = 0x1002;
+pub const VENDOR_INTEL: u16 = 0x8086;
+
+/// A graphics card as the kernel sees it
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Gpu {
+    // card0,card1...
+    pub card: String,
+    pub vendor: u16,
+    pub device: u16,
+    // Kernel driver,e.g. amdgpu,radeon,i915,nouveau,nvidia
+    pub driver: Option<String>,
+    // The card the firmware booted on
+    pub boot_vga: bool,
+}
+impl Gpu {
+    pub fn vendor_name(&self) -> String {
+        match self.vendor {
+            VENDOR_NVIDIA => "NVIDIA".into(),
+            VENDOR_AMD => "AMD/ATI".into(),
+            VENDOR_INTEL => "Intel".into(),
+            x => format!("Vendor {:04x}", x),
+        }
+    }
+}
+
+fn read_hex(path: impl AsRef<Path>) -> Option<u16> {
+    let text = fs::read_to_string(path).ok()?;
+    u16::from_str_radix(text.trim().trim_start_matches("0x"), 16).ok()
+}
+
+// `drm` is /sys/class/drm,tests use a directory of their own
+pub fn list_gpus_in(drm: &Path) -> anyhow::Result<Vec<Gpu>> {
+    let mut gpus = Vec::new();
+    for i in fs::read_dir(drm)? {
+        let card = i?.file_name().to_string_lossy().to_string();
+        // Connectors show up as card0-HDMI-A-1 and so on
+        if !card.starts_with("card") || card.contains('-') {
+            continue;
+        }
+        let device = drm.join(&card).join("device");
+        let Some(vendor) = read_hex(device.join("vendor")) else {
+            continue;
+        };
+        gpus.push(Gpu {
+            card,
+            vendor,
+            device: read_hex(device.join("device")).unwrap_or_default(),
+            driver: fs::read_link(device.join("driver"))
+                .ok()
+                .and_then(|x| x.file_name().map(|x| x.to_string_lossy().to_string())),
+            boot_vga: fs::read_to_string(device.join("boot_vga")).is_ok_and(|x| x.trim() == "1"),
+        });
+    }
+    gpus.sort_by(|a, b| a.card.cmp(&b.card));
+    Ok(gpus)
+}
+pub fn list_gpus() -> anyhow::Result<Vec<Gpu>> {
+    list_gpus_in(Path::new("/sys/class/drm"))
+}
+
+/// The OpenGL driver the X server hands out,from `glxinfo -B`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GlInfo {
+    pub vendor: String,
+    pub renderer: String,
+    pub version: String,
+}
+impl GlInfo {
+    pub fn parse(glxinfo: &str) -> Option<Self> {
+        let field = |name: &str| {
+            glxinfo
+                .lines()
+                .find_map(|x| x.trim().strip_prefix(name))
+                .map(|x| x.trim().to_string())
+        };
+        Some(Self {
+            vendor: field("OpenGL vendor string:")?,
+            renderer: field("OpenGL renderer string:")?,
+            version: field("OpenGL version string:")
+                .or(field("OpenGL core profile version string:"))?,
+        })
+    }
+    pub fn is_mesa(&self) -> bool {
+        self.version.contains("Mesa")
+    }
+    // PCI vendor of the GPU doing the rendering,None for software rendering
+    pub fn gpu_vendor(&self) -> Option<u16> {
+        let text = format!("{} {}", self.vendor, self.renderer).to_uppercase();
+        let has = |words: &[&str]| words.iter().any(|x| text.contains(x));
+        if has(&["LLVMPIPE", "SOFTPIPE"]) {
+            None
+        } else if has(&["NVIDIA", "GEFORCE", "QUADRO"]) {
+            Some(VENDOR_NVIDIA)
+        } else if has(&["AMD", "RADEON", "ATI "]) {
+            Some(VENDOR_AMD)
+        } else if has(&["INTEL"]) {
+            Some(VENDOR_INTEL)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn gl_info() -> Option<GlInfo> {
+    let out = Command::new("glxinfo").arg("-B").output().ok()?;
+    GlInfo::parse(&String::from_utf8_lossy(&out.stdout))
+}
+
+/// What the game is going to render on
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct GpuReport {
+    pub gpus: Vec<Gpu>,
+    // None when glxinfo is missing or there is no X server
+    pub gl: Option<GlInfo>,
+}
+impl GpuReport {
+    pub fn detect() -> Self {
+        Self {
+            gpus: list_gpus().unwrap_or_default(),
+            gl: gl_info(),
+        }
+    }
+    // glxinfo can take a second,so the UI asks for the report on another thread
+    pub fn detect_in_background() -> Receiver<Self> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(Self::detect());
+        });
+        receiver
+    }
+    // The card the OpenGL driver renders on,else the one the machine booted on
+    pub fn active(&self) -> Option<&Gpu> {
+        self.gl
+            .as_ref()
+            .and_then(|gl| gl.gpu_vendor())
+            .and_then(|vendor| self.gpus.iter().find(|x| x.vendor == vendor))
+            .or_else(|| self.gpus.iter().find(|x| x.boot_vga))
+            .or(self.gpus.first())
+    }
+    // Some(true) for Mesa,Some(false) for a proprietary driver
+    pub fn mesa(&self) -> Option<bool> {
+        if let Some(gl) = &self.gl {
+            return Some(gl.is_mesa());
+        }
+        let driver = self.active()?.driver.as_deref()?;
+        Some(!matches!(driver, "nvidia" | "fglrx"))
+    }
+    pub fn is_amd(&self) -> bool {
+        self.active().is_some_and(|x| x.vendor == VENDOR_AMD)
+    }
+    // The GPU_VENDOR that matches this machine
+    pub fn recommend(&self) -> Option<GpuType> {
+        let gpu = self.active()?;
+        Some(match gpu.vendor {
+            VENDOR_NVIDIA => GpuType::Nvidia,
+            VENDOR_INTEL => GpuType::Intel,
+            // Cards older than GCN only run on radeon or the old Catalyst driver
+            VENDOR_AMD => match gpu.driver.as_deref() {
+                Some("radeon") | Some("fglrx") => GpuType::ATI,
+                _ => GpuType::AMD,
+            },
+            _ => GpuType::Unknown,
+        })
+    }
+    pub fn describe(&self) -> String {
+        let Some(gpu) = self.active() else {
+            return "No GPU found".into();
+        };
+        let mut text = gpu.vendor_name();
+        if let Some(driver) = &gpu.driver {
+            text += &format!(" ({})", driver);
+        }
+        match self.mesa() {
+            Some(true) => text += ",Mesa",
+            Some(false) => text += ",proprietary driver",
+            None => {}
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpu_report() {
+        let drm = std::env::temp_dir().join("loader-gui-test-drm");
+        let _ = fs::remove_dir_all(&drm);
+        let card = |name: &str, vendor: &str, driver: &str, boot_vga: &str| {
+            let device = drm.join(name).join("device");
+            fs::create_dir_all(&device).unwrap();
+            fs::write(device.join("vendor"), vendor).unwrap();
+            fs::write(device.join("device"), "0x1234\n").unwrap();
+            fs::write(device.join("boot_vga"), boot_vga).unwrap();
+            let target = drm.join("drivers").join(driver);
+            fs::create_dir_all(&target).unwrap();
+            std::os::unix::fs::symlink(target, device.join("driver")).unwrap();
+        };
+        card("card0", "0x8086\n", "i915", "0\n");
+        card("card1", "0x1002\n", "radeon", "1\n");
+        fs::create_dir_all(drm.join("card1-HDMI-A-1")).unwrap();
+        let gpus = list_gpus_in(&drm).unwrap();
+        assert_eq!(gpus.len(), 2);
+        assert_eq!(gpus[1].driver.as_deref(), Some("radeon"));
+
+        let mut report = GpuReport { gpus, gl: None };
+        assert!(report.is_amd());
+        assert_eq!(report.recommend(), Some(GpuType::ATI));
+        assert_eq!(report.describe(), "AMD/ATI (radeon),Mesa");
+        let glxinfo = "name of display: :0
+    OpenGL vendor string: Intel
+    OpenGL renderer string: Mesa Intel(R) UHD Graphics 620 (KBL GT2)
+    OpenGL core profile version string: 4.6 (Core Profile) Mesa 23.2.1
+    OpenGL version string: 4.6 (Compatibility Profile) Mesa 23.2.1
+";
+        report.gl = GlInfo::parse(glxinfo);
+        assert_eq!(report.active().unwrap().card, "card0");
+        assert_eq!(report.recommend(), Some(GpuType::Intel));
+        assert!(!report.is_amd());
+        fs::remove_dir_all(&drm).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use crate::config::LindberghConfig;
+use crate::games::GameTitle;
+use chrono::Local;
+use std::fs;
+use std::path::PathBuf;
+
+const MAX_UNDO: usize = 100;
+const MAX_REVISIONS: usize = 20;
+
+/// Undo/redo stack over the config being edited in the configure pages
+#[derive(Clone, Default)]
+pub struct EditHistory {
+    undo_stack: Vec<LindberghConfig>,
+    redo_stack: Vec<LindberghConfig>,
+    last: Option<LindberghConfig>,
+}
+impl EditHistory {
+    // Call this once per frame after the config has been edited
+    pub fn record(&mut self, current: &LindberghConfig) {
+        match &self.last {
+            Some(last) if last == current => {}
+            Some(last) => {
+                self.undo_stack.push(last.clone());
+                if self.undo_stack.len() > MAX_UNDO {
+                    self.undo_stack.remove(0);
+                }
+                self.redo_stack.clear();
+                self.last = Some(current.clone());
+            }
+            None => {
+                self.last = Some(current.clone());
+            }
+        }
+    }
+    pub fn undo(&mut self, current: &mut LindberghConfig) -> bool {
+        if let Some(prev) = self.undo_stack.pop() {
+            self.redo_stack.push(current.clone());
+            *current = prev.clone();
+            self.last = Some(prev);
+            true
+        } else {
+            false
+        }
+    }
+    pub fn redo(&mut self, current: &mut LindberghConfig) -> bool {
+        if let Some(next) = self.redo_stack.pop() {
+            self.undo_stack.push(current.clone());
+            *current = next.clone();
+            self.last = Some(next);
+            true
+        } else {
+            false
+        }
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+}
+
+/// Every successful save is copied into ./config/history/<GameTitle>/
+pub mod revisions {
+    use super::*;
+
+    pub fn revision_dir(current_title: &GameTitle) -> PathBuf {
+        PathBuf::from(format!("./config/history/{:?}", current_title))
+    }
+    pub fn save_revision(current_title: &GameTitle) -> anyhow::Result<()> {
+        let dir = revision_dir(current_title);
+        fs::create_dir_all(&dir)?;
+        fs::copy(
+            format!("./config/{:?}.conf", current_title),
+            // Milliseconds keep two saves in the same second apart
+            dir.join(format!(
+                "{}.conf",
+                Local::now().format("%Y-%m-%d %H:%M:%S%.3f")
+            )),
+        )?;
+        for i in list_revisions(current_title)?.iter().skip(MAX_REVISIONS) {
+            fs::remove_file(i)?;
+        }
+        Ok(())
+    }
+    // Newest first
+    pub fn list_revisions(current_title: &GameTitle) -> anyhow::Result<Vec<PathBuf>> {
+        let dir = revision_dir(current_title);
+        if !fs::exists(&dir)? {
+            return Ok(vec![]);
+        }
+        let mut list = Vec::new();
+        for i in fs::read_dir(dir)? {
+            let path = i?.path();
+            if path.extension().is_some_and(|x| x == "conf") {
+                list.push(path);
+            }
+        }
+        list.sort();
+        list.reverse();
+        Ok(list)
+    }
+    pub fn load_revision(path: &PathBuf) -> anyhow::Result<LindberghConfig> {
+        let mut config = LindberghConfig::default();
+        config.read_from_lindbergh_conf_by_path(path)?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = EditHistory::default();
+        let mut config = LindberghConfig::default();
+        history.record(&config);
+        config.fullscreen = true;
+        history.record(&config);
+        config.freeplay = true;
+        history.record(&config);
+        assert!(history.undo(&mut config));
+        assert!(config.fullscreen && !config.freeplay);
+        assert!(history.undo(&mut config));
+        assert!(!config.fullscreen);
+        assert!(!history.undo(&mut config));
+        assert!(history.redo(&mut config));
+        assert!(config.fullscreen);
+        // A new edit drops everything that could be redone
+        config.hide_cursor = false;
+        history.record(&config);
+        assert!(!history.can_redo());
+    }
+}
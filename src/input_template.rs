@@ -0,0 +1,239 @@
+use crate::config::{_SdlInput, KeymapField};
+use crate::games::{GameTitle, GameType};
+use eframe::egui::Key;
+
+/// Describes which JVS inputs a game actually uses and what they do
+/// NOTE: Test/Service/Start/Coin are shared by every game and always shown
+#[derive(Clone)]
+pub struct InputTemplate {
+    pub players: usize,
+    // Game inputs in display order,anything not listed here is hidden on the mapping page
+    pub inputs: Vec<(KeymapField, &'static str)>,
+    // SDL keys bound on top of `_SdlInput::default()`,as (player,field,key)
+    pub bindings: Vec<(usize, KeymapField, Key)>,
+    // What ANALOGUE_1..4 drive,empty if the game has no analogue controls
+    pub analogues: Vec<&'static str>,
+}
+impl InputTemplate {
+    fn generic() -> Self {
+        Self {
+            players: 2,
+            inputs: KeymapField::ALL
+                .into_iter()
+                .filter(|x| !Self::is_system(*x))
+                .map(|x| (x, ""))
+                .collect(),
+            bindings: vec![],
+            analogues: vec![],
+        }
+    }
+    pub fn is_system(field: KeymapField) -> bool {
+        matches!(
+            field,
+            KeymapField::Test | KeymapField::Service | KeymapField::Start | KeymapField::Coin
+        )
+    }
+    // None if the game does not use this input
+    pub fn label(&self, field: KeymapField) -> Option<String> {
+        if Self::is_system(field) {
+            return Some(field.to_string());
+        }
+        self.inputs
+            .iter()
+            .find(|x| x.0 == field)
+            .map(|x| match x.1 {
+                "" => field.to_string(),
+                s => s.to_string(),
+            })
+    }
+    pub fn analogue_label(&self, channel: usize) -> String {
+        self.analogues
+            .get(channel)
+            .map(|x| x.to_string())
+            .unwrap_or(format!("Analogue {}", channel + 1))
+    }
+    // Fields shown for a player,system inputs first
+    pub fn fields(&self, player: usize) -> Vec<KeymapField> {
+        if player > self.players {
+            return vec![];
+        }
+        KeymapField::ALL
+            .into_iter()
+            .filter(|x| Self::is_system(*x) && !(player == 2 && *x == KeymapField::Test))
+            .chain(self.inputs.iter().map(|x| x.0))
+            .collect()
+    }
+    pub fn default_sdl(&self) -> _SdlInput {
+        let mut sdl = _SdlInput::default();
+        for (player, keymap) in [(1, &mut sdl.player1), (2, &mut sdl.player2)] {
+            // Unused optional buttons would only collide with the keys below
+            for n in 5..=8 {
+                let field = KeymapField::Button(n);
+                if player > self.players || self.label(field).is_none() {
+                    match n {
+                        5 => keymap.button5 = None,
+                        6 => keymap.button6 = None,
+                        7 => keymap.button7 = None,
+                        _ => keymap.button8 = None,
+                    }
+                }
+            }
+        }
+        for (player, field, key) in &self.bindings {
+            if *player == 1 {
+                sdl.player1.set(*field, (*key).into());
+            } else {
+                sdl.player2.set(*field, (*key).into());
+            }
+        }
+        sdl
+    }
+}
+impl From<&GameTitle> for InputTemplate {
+    fn from(value: &GameTitle) -> Self {
+        use KeymapField::*;
+        match value {
+            GameTitle::Virtua_Tennis_3 => Self {
+                players: 2,
+                inputs: vec![
+                    (Up, ""),
+                    (Down, ""),
+                    (Left, ""),
+                    (Right, ""),
+                    (Button(1), "Shot"),
+                    (Button(2), "Lob"),
+                ],
+                analogues: vec![],
+                bindings: vec![
+                    (1, Button(1), Key::Z),
+                    (1, Button(2), Key::X),
+                    (2, Button(1), Key::N),
+                    (2, Button(2), Key::M),
+                ],
+            },
+            // Listed as a shooting game,but it's a driving game with a gun
+            GameTitle::R_Tuned => Self::from(&GameType::DRIVING),
+            GameTitle::Primeval_Hunt | GameTitle::Ghost_Squad_Evolution => Self {
+                players: 2,
+                inputs: vec![(Button(1), "Trigger"), (Button(2), "Action")],
+                ..Self::from(&GameType::SHOOTING)
+            },
+            _ => match &value.as_gamedata().game_type {
+                Some(t) => Self::from(t),
+                None => Self::generic(),
+            },
+        }
+    }
+}
+impl From<&GameType> for InputTemplate {
+    fn from(value: &GameType) -> Self {
+        use KeymapField::*;
+        match value {
+            GameType::FIGHTING => Self {
+                players: 2,
+                inputs: vec![
+                    (Up, ""),
+                    (Down, ""),
+                    (Left, ""),
+                    (Right, ""),
+                    (Button(1), "Guard"),
+                    (Button(2), "Punch"),
+                    (Button(3), "Kick"),
+                ],
+                analogues: vec![],
+                bindings: vec![
+                    (1, Button(1), Key::Z),
+                    (1, Button(2), Key::X),
+                    (1, Button(3), Key::C),
+                    (2, Button(1), Key::B),
+                    (2, Button(2), Key::N),
+                    (2, Button(3), Key::M),
+                ],
+            },
+            GameType::DRIVING => Self {
+                players: 1,
+                inputs: vec![
+                    (Up, "Gear Up"),
+                    (Down, "Gear Down"),
+                    (Left, "Gear Left"),
+                    (Right, "Gear Right"),
+                    (Button(1), "View Change"),
+                    (Button(2), "Music Select"),
+                ],
+                bindings: vec![(1, Button(1), Key::V), (1, Button(2), Key::M)],
+                analogues: vec!["Steering", "Gas", "Brake"],
+            },
+            GameType::HARLEY => Self {
+                players: 1,
+                inputs: vec![
+                    (Up, "Gear Up"),
+                    (Down, "Gear Down"),
+                    (Button(1), "View Change"),
+                    (Button(2), "Music Select"),
+                ],
+                bindings: vec![(1, Button(1), Key::V), (1, Button(2), Key::M)],
+                analogues: vec!["Handlebar", "Throttle", "Brake"],
+            },
+            GameType::SHOOTING => Self {
+                players: 2,
+                inputs: vec![(Button(1), "Trigger"), (Button(2), "Reload Pedal")],
+                bindings: vec![
+                    (1, Button(1), Key::Z),
+                    (1, Button(2), Key::X),
+                    (2, Button(1), Key::N),
+                    (2, Button(2), Key::M),
+                ],
+                analogues: vec!["Gun X", "Gun Y", "Player 2 Gun X", "Player 2 Gun Y"],
+            },
+            GameType::ABC => Self {
+                players: 1,
+                inputs: vec![
+                    (Up, "Stick Up"),
+                    (Down, "Stick Down"),
+                    (Left, "Stick Left"),
+                    (Right, "Stick Right"),
+                    (Button(1), "Gun"),
+                    (Button(2), "Missile"),
+                    (Button(3), "View Change"),
+                ],
+                bindings: vec![
+                    (1, Button(1), Key::Z),
+                    (1, Button(2), Key::X),
+                    (1, Button(3), Key::V),
+                ],
+                analogues: vec!["Stick X", "Stick Y", "Throttle"],
+            },
+            GameType::MAHJONG => Self {
+                players: 1,
+                ..Self::generic()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Keymap;
+
+    #[test]
+    fn test_templates() {
+        let vf5 = InputTemplate::from(&GameTitle::Virtua_Fighter_5);
+        assert_eq!(vf5.label(KeymapField::Button(2)).as_deref(), Some("Punch"));
+        assert!(vf5.label(KeymapField::Button(4)).is_none());
+        assert!(!vf5.fields(2).contains(&KeymapField::Test));
+
+        let outrun = InputTemplate::from(&GameTitle::Outrun_2_SP_SDX);
+        assert!(outrun.fields(2).is_empty());
+        let sdl = outrun.default_sdl();
+        assert!(sdl.player1.button1 == Key::V.into());
+        assert!(sdl.player2.button7.is_none());
+        // Generated configs must still be writable,and free of clashing keys
+        for i in GameTitle::all_variants() {
+            let sdl = InputTemplate::from(i).default_sdl();
+            let mut buf = Vec::new();
+            sdl.write_to_lindbergh_conf(&mut buf).unwrap();
+            assert!(Keymap::Sdl(sdl).conflicts().is_empty(), "{:?}", i);
+        }
+    }
+}
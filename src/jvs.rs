@@ -0,0 +1,529 @@
+use crate::serial;
+use anyhow::anyhow;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+pub const SYNC: u8 = 0xE0;
+// Escapes SYNC/MARK inside a packet,the next byte is sent minus one
+pub const MARK: u8 = 0xD0;
+pub const BROADCAST: u8 = 0xFF;
+pub const MASTER: u8 = 0x00;
+
+pub const CMD_RESET: u8 = 0xF0;
+pub const CMD_SET_ADDRESS: u8 = 0xF1;
+pub const CMD_READ_ID: u8 = 0x10;
+pub const CMD_COMMAND_VERSION: u8 = 0x11;
+pub const CMD_JVS_VERSION: u8 = 0x12;
+pub const CMD_COMMS_VERSION: u8 = 0x13;
+pub const CMD_FEATURES: u8 = 0x14;
+pub const CMD_READ_SWITCHES: u8 = 0x20;
+pub const CMD_READ_COINS: u8 = 0x21;
+pub const CMD_READ_ANALOGS: u8 = 0x22;
+
+pub const JVS_BAUD: u32 = 115200;
+
+pub const STATUS_NORMAL: u8 = 0x01;
+pub const REPORT_NORMAL: u8 = 0x01;
+
+// Delay between polls of the inputs,about one frame
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+// Player switches in the order the board reports them,buttons follow
+const SWITCH_NAMES: [&str; 6] = ["Start", "Service", "Up", "Down", "Left", "Right"];
+
+pub fn switch_name(n: usize) -> String {
+    SWITCH_NAMES
+        .get(n)
+        .map(|x| x.to_string())
+        .unwrap_or_else(|| format!("Button {}", n - SWITCH_NAMES.len() + 1))
+}
+
+pub fn encode_packet(node: u8, data: &[u8]) -> Vec<u8> {
+    let len = data.len() as u8 + 1;
+    let sum = data
+        .iter()
+        .fold(node.wrapping_add(len), |a, x| a.wrapping_add(*x));
+    let mut packet = vec![SYNC];
+    for b in [node, len].iter().chain(data).chain([sum].iter()) {
+        if *b == SYNC || *b == MARK {
+            packet.push(MARK);
+            packet.push(b - 1);
+        } else {
+            packet.push(*b);
+        }
+    }
+    packet
+}
+
+fn read_byte(reader: &mut impl Read) -> anyhow::Result<u8> {
+    let mut buf = [0u8];
+    match reader.read(&mut buf)? {
+        0 => Err(anyhow!("No response from the JVS board")),
+        _ => Ok(buf[0]),
+    }
+}
+
+fn read_unescaped(reader: &mut impl Read) -> anyhow::Result<u8> {
+    match read_byte(reader)? {
+        SYNC => Err(anyhow!("Unexpected start of packet")),
+        MARK => Ok(read_byte(reader)?.wrapping_add(1)),
+        b => Ok(b),
+    }
+}
+
+/// Reads the next packet,as (node,data)
+pub fn read_packet(reader: &mut impl Read) -> anyhow::Result<(u8, Vec<u8>)> {
+    while read_byte(reader)? != SYNC {}
+    let node = read_unescaped(reader)?;
+    let len = read_unescaped(reader)?;
+    if len == 0 {
+        return Err(anyhow!("Packet with a length of 0"));
+    }
+    let mut data = (0..len)
+        .map(|_| read_unescaped(reader))
+        .collect::<anyhow::Result<Vec<u8>>>()?;
+    let sum = data.pop().unwrap();
+    let expected = data
+        .iter()
+        .fold(node.wrapping_add(len), |a, x| a.wrapping_add(*x));
+    if sum != expected {
+        return Err(anyhow!(
+            "Checksum mismatch: got {:02X},expected {:02X}",
+            sum,
+            expected
+        ));
+    }
+    Ok((node, data))
+}
+
+// Splits the next n bytes off a response
+fn take<'a>(data: &mut &'a [u8], n: usize) -> anyhow::Result<&'a [u8]> {
+    if data.len() < n {
+        return Err(anyhow!("Response from the JVS board is too short"));
+    }
+    let (head, tail) = data.split_at(n);
+    *data = tail;
+    Ok(head)
+}
+
+fn take_report(data: &mut &[u8], cmd: u8) -> anyhow::Result<()> {
+    match take(data, 1)?[0] {
+        REPORT_NORMAL => Ok(()),
+        r => Err(anyhow!(
+            "The board rejected command {:02X} (report {:02X})",
+            cmd,
+            r
+        )),
+    }
+}
+
+/// What the board says it has,from the feature check
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Capabilities {
+    pub players: u8,
+    // Per player
+    pub switches: u8,
+    pub coins: u8,
+    pub analogs: u8,
+    pub analog_bits: u8,
+    pub rotaries: u8,
+    pub keypad: bool,
+    // (x bits,y bits,channels) of the lightgun inputs
+    pub screen_position: Option<(u8, u8, u8)>,
+    pub misc_switches: u16,
+    pub card_slots: u8,
+    pub hoppers: u8,
+    pub general_outputs: u8,
+    pub analog_outputs: u8,
+    pub character_display: Option<(u8, u8)>,
+    pub backup: bool,
+}
+impl Capabilities {
+    pub fn parse(mut data: &[u8]) -> anyhow::Result<Self> {
+        let mut caps = Self::default();
+        loop {
+            let f = take(&mut data, 1)?[0];
+            if f == 0x00 {
+                break;
+            }
+            let p = take(&mut data, 3)?;
+            match f {
+                0x01 => {
+                    caps.players = p[0];
+                    caps.switches = p[1];
+                }
+                0x02 => caps.coins = p[0],
+                0x03 => {
+                    caps.analogs = p[0];
+                    caps.analog_bits = p[1];
+                }
+                0x04 => caps.rotaries = p[0],
+                0x05 => caps.keypad = true,
+                0x06 => caps.screen_position = Some((p[0], p[1], p[2])),
+                0x07 => caps.misc_switches = u16::from_be_bytes([p[0], p[1]]),
+                0x10 => caps.card_slots = p[0],
+                0x11 => caps.hoppers = p[0],
+                0x12 => caps.general_outputs = p[0],
+                0x13 => caps.analog_outputs = p[0],
+                0x14 => caps.character_display = Some((p[0], p[1])),
+                0x15 => caps.backup = true,
+                // Unknown functions are skipped
+                _ => {}
+            }
+        }
+        Ok(caps)
+    }
+    pub fn switch_bytes(&self) -> u8 {
+        self.switches.div_ceil(8)
+    }
+    // One line per feature,for display
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![];
+        if self.players > 0 {
+            lines.push(format!(
+                "{} player(s),{} switches each",
+                self.players, self.switches
+            ));
+        }
+        if self.coins > 0 {
+            lines.push(format!("{} coin slot(s)", self.coins));
+        }
+        if self.analogs > 0 {
+            lines.push(format!(
+                "{} analog channel(s),{} bits",
+                self.analogs, self.analog_bits
+            ));
+        }
+        if self.rotaries > 0 {
+            lines.push(format!("{} rotary encoder(s)", self.rotaries));
+        }
+        if self.keypad {
+            lines.push("Keypad".to_string());
+        }
+        if let Some((x, y, n)) = self.screen_position {
+            lines.push(format!("{} gun channel(s),{}x{} bits", n, x, y));
+        }
+        if self.misc_switches > 0 {
+            lines.push(format!("{} misc switches", self.misc_switches));
+        }
+        if self.card_slots > 0 {
+            lines.push(format!("{} card slot(s)", self.card_slots));
+        }
+        if self.hoppers > 0 {
+            lines.push(format!("{} hopper(s)", self.hoppers));
+        }
+        if self.general_outputs > 0 {
+            lines.push(format!("{} general output(s)", self.general_outputs));
+        }
+        if self.analog_outputs > 0 {
+            lines.push(format!("{} analog output(s)", self.analog_outputs));
+        }
+        if let Some((w, h)) = self.character_display {
+            lines.push(format!("{}x{} character display", w, h));
+        }
+        if self.backup {
+            lines.push("Backup data".to_string());
+        }
+        lines
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct BoardInfo {
+    pub id: String,
+    // BCD,0x13 is 1.3
+    pub command_version: u8,
+    pub jvs_version: u8,
+    pub comms_version: u8,
+    pub capabilities: Capabilities,
+}
+
+pub fn bcd_version(v: u8) -> String {
+    format!("{}.{}", v >> 4, v & 0x0F)
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct InputState {
+    pub system: u8,
+    // Raw switch bytes of each player
+    pub switches: Vec<Vec<u8>>,
+    pub coins: Vec<u16>,
+    pub analogs: Vec<u16>,
+}
+impl InputState {
+    pub fn test(&self) -> bool {
+        self.system & 0x80 != 0
+    }
+    // n counts from the most significant bit of the first byte
+    pub fn switch(&self, player: usize, n: usize) -> bool {
+        self.switches
+            .get(player)
+            .and_then(|x| x.get(n / 8))
+            .is_some_and(|x| x & (0x80 >> (n % 8)) != 0)
+    }
+}
+
+/// A JVS I/O board on the other end of a serial port
+pub struct JvsBoard<T: Read + Write> {
+    port: T,
+    address: u8,
+}
+impl<T: Read + Write> JvsBoard<T> {
+    // Only the first board of the chain is set up,it becomes node 1
+    pub fn handshake(mut port: T) -> anyhow::Result<(Self, BoardInfo)> {
+        // Sent twice as the spec asks,nothing answers it
+        for _ in 0..2 {
+            port.write_all(&encode_packet(BROADCAST, &[CMD_RESET, 0xD9]))?;
+        }
+        port.flush()?;
+        thread::sleep(Duration::from_millis(100));
+        let mut board = Self { port, address: 1 };
+        let r = board.request(BROADCAST, &[CMD_SET_ADDRESS, board.address])?;
+        take_report(&mut r.as_slice(), CMD_SET_ADDRESS)
+            .map_err(|e| anyhow!("Unable to set the board address: {}", e))?;
+
+        let r = board.request(
+            board.address,
+            &[
+                CMD_READ_ID,
+                CMD_COMMAND_VERSION,
+                CMD_JVS_VERSION,
+                CMD_COMMS_VERSION,
+                CMD_FEATURES,
+            ],
+        )?;
+        let mut data = r.as_slice();
+        take_report(&mut data, CMD_READ_ID)?;
+        let end = data
+            .iter()
+            .position(|x| *x == 0)
+            .ok_or(anyhow!("Board ID is not terminated"))?;
+        let id = String::from_utf8_lossy(take(&mut data, end + 1)?[..end].as_ref()).to_string();
+        let mut versions = [0u8; 3];
+        for (cmd, v) in [CMD_COMMAND_VERSION, CMD_JVS_VERSION, CMD_COMMS_VERSION]
+            .into_iter()
+            .zip(versions.iter_mut())
+        {
+            take_report(&mut data, cmd)?;
+            *v = take(&mut data, 1)?[0];
+        }
+        take_report(&mut data, CMD_FEATURES)?;
+        let info = BoardInfo {
+            id,
+            command_version: versions[0],
+            jvs_version: versions[1],
+            comms_version: versions[2],
+            capabilities: Capabilities::parse(data)?,
+        };
+        Ok((board, info))
+    }
+    // Sends a packet and returns the data of the reply,past the status byte
+    fn request(&mut self, node: u8, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.port.write_all(&encode_packet(node, data))?;
+        self.port.flush()?;
+        let (to, mut reply) = read_packet(&mut self.port)?;
+        if to != MASTER {
+            return Err(anyhow!("Reply was sent to node {:02X}", to));
+        }
+        if reply.is_empty() {
+            return Err(anyhow!("Empty reply from the JVS board"));
+        }
+        match reply.remove(0) {
+            STATUS_NORMAL => Ok(reply),
+            0x02 => Err(anyhow!("The board does not know the command")),
+            0x03 => Err(anyhow!("The board received a corrupted packet")),
+            0x04 => Err(anyhow!("The board is busy")),
+            s => Err(anyhow!("Unknown status {:02X}", s)),
+        }
+    }
+    pub fn read_inputs(&mut self, caps: &Capabilities) -> anyhow::Result<InputState> {
+        let mut cmds = vec![CMD_READ_SWITCHES, caps.players, caps.switch_bytes()];
+        if caps.coins > 0 {
+            cmds.extend([CMD_READ_COINS, caps.coins]);
+        }
+        if caps.analogs > 0 {
+            cmds.extend([CMD_READ_ANALOGS, caps.analogs]);
+        }
+        let r = self.request(self.address, &cmds)?;
+        let mut data = r.as_slice();
+        let mut state = InputState::default();
+        take_report(&mut data, CMD_READ_SWITCHES)?;
+        state.system = take(&mut data, 1)?[0];
+        for _ in 0..caps.players {
+            state
+                .switches
+                .push(take(&mut data, caps.switch_bytes() as usize)?.to_vec());
+        }
+        if caps.coins > 0 {
+            take_report(&mut data, CMD_READ_COINS)?;
+            for _ in 0..caps.coins {
+                let c = take(&mut data, 2)?;
+                // The top 2 bits are the slot condition
+                state.coins.push(u16::from_be_bytes([c[0], c[1]]) & 0x3FFF);
+            }
+        }
+        if caps.analogs > 0 {
+            take_report(&mut data, CMD_READ_ANALOGS)?;
+            for _ in 0..caps.analogs {
+                let c = take(&mut data, 2)?;
+                state.analogs.push(u16::from_be_bytes([c[0], c[1]]));
+            }
+        }
+        Ok(state)
+    }
+}
+
+pub enum JvsMessage {
+    Info(BoardInfo),
+    Inputs(InputState),
+    Error(String),
+}
+
+/// Talks to the board on another thread,so the UI can show its state every frame
+pub struct JvsMonitor {
+    pub path: PathBuf,
+    receiver: Receiver<JvsMessage>,
+    pub info: Option<BoardInfo>,
+    pub inputs: Option<InputState>,
+    pub error: Option<String>,
+}
+impl JvsMonitor {
+    pub fn start(path: &Path) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let p = path.to_path_buf();
+        thread::spawn(move || {
+            let run = || -> anyhow::Result<()> {
+                let (mut board, info) = JvsBoard::handshake(serial::open_port(&p, JVS_BAUD)?)?;
+                let caps = info.capabilities.clone();
+                if sender.send(JvsMessage::Info(info)).is_err() {
+                    return Ok(());
+                }
+                loop {
+                    // The monitor has been dropped
+                    if sender
+                        .send(JvsMessage::Inputs(board.read_inputs(&caps)?))
+                        .is_err()
+                    {
+                        return Ok(());
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+            };
+            if let Err(e) = run() {
+                let _ = sender.send(JvsMessage::Error(e.to_string()));
+            }
+        });
+        Self {
+            path: path.to_path_buf(),
+            receiver,
+            info: None,
+            inputs: None,
+            error: None,
+        }
+    }
+    pub fn update(&mut self) {
+        for i in self.receiver.try_iter() {
+            match i {
+                JvsMessage::Info(x) => self.info = Some(x),
+                JvsMessage::Inputs(x) => self.inputs = Some(x),
+                JvsMessage::Error(x) => self.error = Some(x),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial::open_pty;
+    use std::fs::File;
+    use std::time::Instant;
+
+    // Answers like a 2 player board with a coin slot and 2 analog channels
+    fn stand_in_board(mut port: File) {
+        let mut address = None;
+        while let Ok((node, data)) = read_packet(&mut port) {
+            let mut reply = vec![STATUS_NORMAL];
+            match (node, data.as_slice()) {
+                (BROADCAST, [CMD_RESET, 0xD9]) => continue,
+                (BROADCAST, [CMD_SET_ADDRESS, a]) => {
+                    address = Some(*a);
+                    reply.push(REPORT_NORMAL);
+                }
+                (n, mut cmds) if Some(n) == address => {
+                    while let Some((cmd, rest)) = cmds.split_first() {
+                        reply.push(REPORT_NORMAL);
+                        cmds = rest;
+                        match *cmd {
+                            CMD_READ_ID => reply.extend(b"SEGA ENTERPRISES,LTD.;I/O BD JVS\0"),
+                            CMD_COMMAND_VERSION => reply.push(0x13),
+                            CMD_JVS_VERSION => reply.push(0x30),
+                            CMD_COMMS_VERSION => reply.push(0x10),
+                            CMD_FEATURES => {
+                                reply.extend([0x01, 2, 13, 0, 0x02, 1, 0, 0, 0x03, 2, 10, 0, 0x00])
+                            }
+                            CMD_READ_SWITCHES => {
+                                cmds = &cmds[2..];
+                                // Test,P1 start and P2 button 1
+                                reply.extend([0x80, 0x80, 0x00, 0x02, 0x00]);
+                            }
+                            CMD_READ_COINS => {
+                                cmds = &cmds[1..];
+                                // 0xE0 and 0xD0 have to be escaped
+                                reply.extend([0x00, 0xE0]);
+                            }
+                            CMD_READ_ANALOGS => {
+                                cmds = &cmds[1..];
+                                reply.extend([0x80, 0x00, 0xD0, 0x40]);
+                            }
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+                _ => continue,
+            }
+            port.write_all(&encode_packet(MASTER, &reply)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_pty_board() {
+        let packet = encode_packet(0x01, &[0xE0, 0xD0, 0x10]);
+        assert_eq!(packet[..3], [SYNC, 0x01, 0x04]);
+        assert_eq!(packet[3..7], [MARK, 0xDF, MARK, 0xCF]);
+        assert_eq!(
+            read_packet(&mut packet.as_slice()).unwrap(),
+            (0x01, vec![0xE0, 0xD0, 0x10])
+        );
+
+        let (master, path) = open_pty();
+        thread::spawn(move || stand_in_board(master));
+        let mut monitor = JvsMonitor::start(&path);
+        let start = Instant::now();
+        while monitor.inputs.is_none() && monitor.error.is_none() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+            monitor.update();
+        }
+        assert_eq!(monitor.error, None);
+        let info = monitor.info.unwrap();
+        assert_eq!(info.id, "SEGA ENTERPRISES,LTD.;I/O BD JVS");
+        assert_eq!(bcd_version(info.command_version), "1.3");
+        assert_eq!(info.capabilities.players, 2);
+        assert_eq!(info.capabilities.switch_bytes(), 2);
+        assert_eq!(info.capabilities.analog_bits, 10);
+        let inputs = monitor.inputs.unwrap();
+        assert!(inputs.test());
+        assert!(inputs.switch(0, 0));
+        assert!(!inputs.switch(0, 6));
+        assert!(inputs.switch(1, 6));
+        assert_eq!(switch_name(0), "Start");
+        assert_eq!(switch_name(5), "Right");
+        assert_eq!(switch_name(6), "Button 1");
+        assert_eq!(inputs.coins, vec![0xE0]);
+        assert_eq!(inputs.analogs, vec![0x8000, 0xD040]);
+    }
+}
@@ -0,0 +1,205 @@
+use eframe::egui::Key;
+
+/// X11 keycodes (evdev code + 8) of every egui key
+/// Symbols typed with shift share the keycode of their unshifted key,the first entry wins when reading
+/// NOTE: F25~F35 have no keycode on any keyboard X11 knows about,so they can't be bound
+const KEY_TABLE: &[(Key, u32)] = &[
+    (Key::Escape, 9),
+    (Key::Num1, 10),
+    (Key::Num2, 11),
+    (Key::Num3, 12),
+    (Key::Num4, 13),
+    (Key::Num5, 14),
+    (Key::Num6, 15),
+    (Key::Num7, 16),
+    (Key::Num8, 17),
+    (Key::Num9, 18),
+    (Key::Num0, 19),
+    (Key::Minus, 20),
+    (Key::Equals, 21),
+    (Key::Backspace, 22),
+    (Key::Tab, 23),
+    (Key::Q, 24),
+    (Key::W, 25),
+    (Key::E, 26),
+    (Key::R, 27),
+    (Key::T, 28),
+    (Key::Y, 29),
+    (Key::U, 30),
+    (Key::I, 31),
+    (Key::O, 32),
+    (Key::P, 33),
+    (Key::OpenBracket, 34),
+    (Key::CloseBracket, 35),
+    (Key::Enter, 36),
+    (Key::A, 38),
+    (Key::S, 39),
+    (Key::D, 40),
+    (Key::F, 41),
+    (Key::G, 42),
+    (Key::H, 43),
+    (Key::J, 44),
+    (Key::K, 45),
+    (Key::L, 46),
+    (Key::Semicolon, 47),
+    (Key::Quote, 48),
+    (Key::Backtick, 49),
+    (Key::Backslash, 51),
+    (Key::Z, 52),
+    (Key::X, 53),
+    (Key::C, 54),
+    (Key::V, 55),
+    (Key::B, 56),
+    (Key::N, 57),
+    (Key::M, 58),
+    (Key::Comma, 59),
+    (Key::Period, 60),
+    (Key::Slash, 61),
+    (Key::Space, 65),
+    (Key::F1, 67),
+    (Key::F2, 68),
+    (Key::F3, 69),
+    (Key::F4, 70),
+    (Key::F5, 71),
+    (Key::F6, 72),
+    (Key::F7, 73),
+    (Key::F8, 74),
+    (Key::F9, 75),
+    (Key::F10, 76),
+    (Key::F11, 95),
+    (Key::F12, 96),
+    (Key::Home, 110),
+    (Key::ArrowUp, 111),
+    (Key::PageUp, 112),
+    (Key::ArrowLeft, 113),
+    (Key::ArrowRight, 114),
+    (Key::End, 115),
+    (Key::ArrowDown, 116),
+    (Key::PageDown, 117),
+    (Key::Insert, 118),
+    (Key::Delete, 119),
+    (Key::Copy, 141),
+    (Key::Paste, 143),
+    (Key::Cut, 145),
+    (Key::F13, 191),
+    (Key::F14, 192),
+    (Key::F15, 193),
+    (Key::F16, 194),
+    (Key::F17, 195),
+    (Key::F18, 196),
+    (Key::F19, 197),
+    (Key::F20, 198),
+    (Key::F21, 199),
+    (Key::F22, 200),
+    (Key::F23, 201),
+    (Key::F24, 202),
+    // Shifted symbols
+    (Key::Exclamationmark, 10),
+    (Key::Plus, 21),
+    (Key::OpenCurlyBracket, 34),
+    (Key::CloseCurlyBracket, 35),
+    (Key::Colon, 47),
+    (Key::Pipe, 51),
+    (Key::Questionmark, 61),
+];
+
+/// Keys egui has no `Key` for,named after their X11 keysyms
+const RAW_NAMES: &[(u32, &str)] = &[
+    (37, "Control_L"),
+    (50, "Shift_L"),
+    (62, "Shift_R"),
+    (63, "KP_Multiply"),
+    (64, "Alt_L"),
+    (66, "Caps_Lock"),
+    (77, "Num_Lock"),
+    (78, "Scroll_Lock"),
+    (79, "KP_7"),
+    (80, "KP_8"),
+    (81, "KP_9"),
+    (82, "KP_Subtract"),
+    (83, "KP_4"),
+    (84, "KP_5"),
+    (85, "KP_6"),
+    (86, "KP_Add"),
+    (87, "KP_1"),
+    (88, "KP_2"),
+    (89, "KP_3"),
+    (90, "KP_0"),
+    (91, "KP_Decimal"),
+    (94, "Less"),
+    (104, "KP_Enter"),
+    (105, "Control_R"),
+    (106, "KP_Divide"),
+    (107, "Print"),
+    (108, "Alt_R"),
+    (127, "Pause"),
+    (133, "Super_L"),
+    (134, "Super_R"),
+    (135, "Menu"),
+];
+
+pub const KEYCODE_SHIFT: u32 = 50;
+pub const KEYCODE_CONTROL: u32 = 37;
+pub const KEYCODE_ALT: u32 = 64;
+
+pub fn key_to_keycode(key: &Key) -> Option<u32> {
+    KEY_TABLE.iter().find(|x| x.0 == *key).map(|x| x.1)
+}
+pub fn keycode_to_key(keycode: u32) -> Option<Key> {
+    KEY_TABLE.iter().find(|x| x.1 == keycode).map(|x| x.0)
+}
+
+/// A key SDL/X11 input can be bound to
+/// Keycodes egui has no key for (modifiers,keypad...) are kept as they are
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SdlKey {
+    Key(Key),
+    Raw(u32),
+}
+impl SdlKey {
+    pub fn from_keycode(keycode: u32) -> Self {
+        keycode_to_key(keycode).map_or(Self::Raw(keycode), Self::Key)
+    }
+    pub fn keycode(&self) -> Option<u32> {
+        match self {
+            Self::Key(k) => key_to_keycode(k),
+            Self::Raw(c) => Some(*c),
+        }
+    }
+    pub fn name(&self) -> String {
+        match self {
+            Self::Key(k) => k.name().to_string(),
+            Self::Raw(c) => RAW_NAMES
+                .iter()
+                .find(|x| x.0 == *c)
+                .map(|x| x.1.to_string())
+                .unwrap_or(format!("Keycode {}", c)),
+        }
+    }
+}
+impl From<Key> for SdlKey {
+    fn from(value: Key) -> Self {
+        Self::Key(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_table() {
+        for k in Key::ALL {
+            let Some(code) = key_to_keycode(k) else {
+                assert!(k.name().starts_with('F') && k.name()[1..].parse::<u32>().unwrap() > 24);
+                continue;
+            };
+            // Shifted symbols read back as their unshifted key
+            assert_eq!(key_to_keycode(&keycode_to_key(code).unwrap()), Some(code));
+        }
+        assert_eq!(SdlKey::from_keycode(50), SdlKey::Raw(50));
+        assert_eq!(SdlKey::from_keycode(50).name(), "Shift_L");
+        assert_eq!(SdlKey::from_keycode(255).keycode(), Some(255));
+        assert_eq!(SdlKey::from_keycode(191), SdlKey::Key(Key::F13));
+    }
+}
@@ -1,4 +1,19 @@
+pub mod cards;
 pub mod config;
+pub mod discovery;
+pub mod display;
+pub mod evdev;
+pub mod gamepad;
 pub mod games;
+pub mod gpu;
+pub mod history;
+pub mod input_template;
+pub mod jvs;
+pub mod keycode;
+pub mod link;
+pub mod loader_info;
+pub mod preflight;
+pub mod resolution;
 pub mod runner;
+pub mod serial;
 pub mod ui;
@@ -0,0 +1,274 @@
+use crate::config::LindberghConfig;
+use crate::games::GameTitle;
+use anyhow::anyhow;
+use network_interface::{Addr, NetworkInterface};
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+/// Games that can be linked with other cabinets,and how they are told about each other
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LinkKind {
+    // Every cabinet only knows its own IP (OR2_IP)
+    Outrun,
+    // Both seats share ID_IP_SEAT_1/2
+    InitialD,
+    // Every cabinet shares HARLEY_CAB1~4
+    Harley,
+}
+impl LinkKind {
+    pub fn from_title(title: &GameTitle) -> Option<Self> {
+        match title {
+            GameTitle::Outrun_2_SP_SDX => Some(Self::Outrun),
+            GameTitle::InitialD_4
+            | GameTitle::InitalD_4_Export
+            | GameTitle::InitialD_5_Japan
+            | GameTitle::InitalD_5_Export_Ver_2
+            | GameTitle::InitalD_5_Export_Ver_4 => Some(Self::InitialD),
+            GameTitle::Harley_Davidson => Some(Self::Harley),
+            _ => None,
+        }
+    }
+    // (min,max) number of linked cabinets
+    pub fn cabinets(&self) -> (usize, usize) {
+        match self {
+            Self::Outrun | Self::Harley => (2, 4),
+            Self::InitialD => (2, 2),
+        }
+    }
+    // Seat IPs already in the config
+    pub fn seats(&self, config: &LindberghConfig) -> Vec<String> {
+        match self {
+            Self::Outrun => vec![config.outrun_link_ip.clone()],
+            Self::InitialD => config.id45_ip_seat.to_vec(),
+            // Empty entries are cabinets that aren't linked
+            Self::Harley => config
+                .harley_cab
+                .iter()
+                .filter(|x| !x.is_empty())
+                .cloned()
+                .collect(),
+        }
+    }
+    // The NIC holding one of the seat IPs,for configs that don't name one
+    pub fn find_nic(
+        &self,
+        config: &LindberghConfig,
+        interfaces: &[NetworkInterface],
+    ) -> Option<String> {
+        let seats = self.seats(config);
+        interfaces
+            .iter()
+            .find(|x| {
+                Subnet::of(x).is_some_and(|s| seats.iter().any(|ip| ip.trim() == s.ip.to_string()))
+            })
+            .map(|x| x.name.clone())
+    }
+}
+
+/// Address and netmask of the NIC the link runs on
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Subnet {
+    pub ip: Ipv4Addr,
+    pub netmask: Ipv4Addr,
+}
+impl Subnet {
+    // The first IPv4 address of the NIC
+    pub fn of(interface: &NetworkInterface) -> Option<Self> {
+        interface.addr.iter().find_map(|x| match x {
+            Addr::V4(v4) => Some(Self {
+                ip: v4.ip,
+                netmask: v4.netmask?,
+            }),
+            _ => None,
+        })
+    }
+    fn network(&self, ip: Ipv4Addr) -> u32 {
+        u32::from(ip) & u32::from(self.netmask)
+    }
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        self.network(ip) == self.network(self.ip)
+    }
+    // Neither the network nor the broadcast address
+    pub fn is_host(&self, ip: Ipv4Addr) -> bool {
+        let host = u32::from(ip) & !u32::from(self.netmask);
+        self.contains(ip) && host != 0 && host != !u32::from(self.netmask)
+    }
+    pub fn broadcast(&self) -> Ipv4Addr {
+        Ipv4Addr::from(u32::from(self.ip) | !u32::from(self.netmask))
+    }
+    pub fn prefix_len(&self) -> u32 {
+        u32::from(self.netmask).count_ones()
+    }
+}
+impl std::fmt::Display for Subnet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.ip, self.prefix_len())
+    }
+}
+
+/// IPs of every cabinet in the link,in seat order
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LinkPlan {
+    pub kind: LinkKind,
+    pub subnet: Subnet,
+    pub seats: Vec<Ipv4Addr>,
+}
+impl LinkPlan {
+    pub fn parse(kind: LinkKind, subnet: Subnet, seats: &[String]) -> anyhow::Result<Self> {
+        Ok(Self {
+            kind,
+            subnet,
+            seats: seats
+                .iter()
+                .enumerate()
+                .map(|(cnt, x)| {
+                    x.trim()
+                        .parse()
+                        .map_err(|_| anyhow!("Seat {}: \"{}\" is not an IPv4 address", cnt + 1, x))
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+    // This machine takes seat 1,the others follow it
+    pub fn suggest(kind: LinkKind, subnet: Subnet, count: usize) -> Self {
+        let seats = (u32::from(subnet.ip)..)
+            .map(Ipv4Addr::from)
+            .take_while(|x| subnet.contains(*x))
+            .filter(|x| subnet.is_host(*x))
+            .take(count)
+            .collect();
+        Self {
+            kind,
+            subnet,
+            seats,
+        }
+    }
+    pub fn this_seat(&self) -> Option<usize> {
+        self.seats.iter().position(|x| *x == self.subnet.ip)
+    }
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let (min, max) = self.kind.cabinets();
+        if self.seats.len() < min || self.seats.len() > max {
+            problems.push(format!("Link {} to {} cabinets", min, max));
+        }
+        for (cnt, ip) in self.seats.iter().enumerate() {
+            if !self.subnet.is_host(*ip) {
+                problems.push(format!(
+                    "Seat {}: {} is not a host on {}",
+                    cnt + 1,
+                    ip,
+                    self.subnet
+                ));
+            }
+            if self.seats[..cnt].contains(ip) {
+                problems.push(format!("Seat {}: {} is already used", cnt + 1, ip));
+            }
+        }
+        if self.this_seat().is_none() {
+            problems.push(format!("No seat uses this machine's IP {}", self.subnet.ip));
+        }
+        problems
+    }
+    // The config of the cabinet at `seat`
+    pub fn apply(&self, config: &mut LindberghConfig, seat: usize) {
+        match self.kind {
+            LinkKind::Outrun => config.outrun_link_ip = self.seats[seat].to_string(),
+            LinkKind::InitialD => {
+                for (i, ip) in config.id45_ip_seat.iter_mut().zip(&self.seats) {
+                    *i = ip.to_string();
+                }
+            }
+            // Cabinets left out of the link must not keep an old IP
+            LinkKind::Harley => {
+                for (cnt, i) in config.harley_cab.iter_mut().enumerate() {
+                    *i = self
+                        .seats
+                        .get(cnt)
+                        .map(|x| x.to_string())
+                        .unwrap_or_default();
+                }
+            }
+        }
+    }
+    // Writes <GameTitle>-seat<n>.conf for every other cabinet
+    pub fn export(
+        &self,
+        config: &LindberghConfig,
+        current_title: &GameTitle,
+        dir: &Path,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for seat in (0..self.seats.len()).filter(|x| Some(*x) != self.this_seat()) {
+            let mut config = config.clone();
+            self.apply(&mut config, seat);
+            let path = dir.join(format!("{:?}-seat{}.conf", current_title, seat + 1));
+            config.write_to_lindbergh_conf_by_path(&path, current_title)?;
+            files.push(path);
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_link_plan() {
+        let subnet = Subnet {
+            ip: Ipv4Addr::new(192, 168, 1, 253),
+            netmask: Ipv4Addr::new(255, 255, 255, 0),
+        };
+        let plan = LinkPlan::suggest(LinkKind::InitialD, subnet, 2);
+        assert_eq!(plan.seats[1], Ipv4Addr::new(192, 168, 1, 254));
+        assert!(plan.problems().is_empty());
+        // Not enough hosts left on the subnet
+        let plan = LinkPlan::suggest(LinkKind::Harley, subnet, 4);
+        assert_eq!(plan.seats.len(), 2);
+
+        let seats = ["192.168.1.253".to_string(), "192.168.2.3".into()];
+        let plan = LinkPlan::parse(LinkKind::Harley, subnet, &seats).unwrap();
+        assert_eq!(plan.problems().len(), 1);
+        let seats = ["192.168.1.3".to_string(), "192.168.1.3".into()];
+        let plan = LinkPlan::parse(LinkKind::InitialD, subnet, &seats).unwrap();
+        assert_eq!(plan.problems().len(), 2);
+        assert!(LinkPlan::parse(LinkKind::InitialD, subnet, &["1.2.3".into()]).is_err());
+
+        let dir = std::env::temp_dir().join("loader-gui-test-link");
+        fs::create_dir_all(&dir).unwrap();
+        let title = GameTitle::InitialD_4;
+        let seats = ["192.168.1.4".to_string(), "192.168.1.253".into()];
+        let plan = LinkPlan::parse(LinkKind::InitialD, subnet, &seats).unwrap();
+        let files = plan
+            .export(&LindberghConfig::default(), &title, &dir)
+            .unwrap();
+        assert_eq!(files, vec![dir.join("InitialD_4-seat1.conf")]);
+        let mut config = LindberghConfig::default();
+        config.read_from_lindbergh_conf_by_path(&files[0]).unwrap();
+        assert_eq!(config.id45_ip_seat, seats);
+
+        let seats = ["192.168.1.4".to_string(), "192.168.1.253".into()];
+        let plan = LinkPlan::parse(LinkKind::Harley, subnet, &seats).unwrap();
+        let files = plan
+            .export(
+                &LindberghConfig::default(),
+                &GameTitle::Harley_Davidson,
+                &dir,
+            )
+            .unwrap();
+        let mut config = LindberghConfig::default();
+        config.read_from_lindbergh_conf_by_path(&files[0]).unwrap();
+        assert_eq!(
+            config.harley_cab,
+            [
+                seats[0].clone(),
+                seats[1].clone(),
+                String::new(),
+                String::new()
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
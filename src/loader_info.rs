@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+use std::fs;
+
+const LOADER_PATH: &str = "./dynlibs/lindbergh.so";
+const DOCS_PATHS: [&str; 2] = ["./dynlibs/lindbergh.conf", "./dynlibs/docs/lindbergh.conf"];
+
+/// General options written by `LindberghConfig::write_to_lindbergh_conf`
+/// NOTE: Input keys are not listed because the loader builds some of them at runtime
+pub const KNOWN_KEYS: &[&str] = &[
+    "WIDTH",
+    "HEIGHT",
+    "FULLSCREEN",
+    "INPUT_MODE",
+    "NO_SDL",
+    "REGION",
+    "FREEPLAY",
+    "EMULATE_JVS",
+    "EMULATE_RIDEBOARD",
+    "EMULATE_MOTIONBOARD",
+    "EMULATE_DRIVEBOARD",
+    "JVS_PATH",
+    "SERIAL_1_PATH",
+    "SERIAL_2_PATH",
+    "SRAM_PATH",
+    "EEPROM_PATH",
+    "GPU_VENDOR",
+    "DEBUG_MSGS",
+    "BORDER_ENABLED",
+    "WHITE_BORDER_PERCENTAGE",
+    "BLACK_BORDER_PERCENTAGE",
+    "HUMMER_FLICKER_FIX",
+    "KEEP_ASPECT_RATIO",
+    "OUTRUN_LENS_GLARE_ENABLED",
+    "SKIP_OUTRUN_CABINET_CHECK",
+    "FPS_LIMITER_ENABLED",
+    "FPS_TARGET",
+    "LGJ_RENDER_WITH_MESA",
+    "PRIMEVAL_HUNT_MODE",
+    "LINDBERGH_COLOUR",
+    "MJ4_ENABLED_ALL_THE_TIME",
+    "DISABLE_BUILTIN_FONT",
+    "DISABLE_BUILTIN_LOGOS",
+    "HIDE_CURSOR",
+    "CUSTOM_CURSOR",
+    "CUSTOM_CURSOR_WIDTH",
+    "CUSTOM_CURSOR_HEIGHT",
+    "ID_IP_SEAT_1",
+    "ID_IP_SEAT_2",
+    "OR2_IP",
+    "CPU_FREQ_GHZ",
+    "EMULATE_CARDREADER",
+    "CARDFILE_01",
+    "CARDFILE_02",
+    "HARLEY_CAB1",
+    "HARLEY_CAB2",
+    "HARLEY_CAB3",
+    "HARLEY_CAB4",
+];
+
+/// What we know about the lindbergh-loader in ./dynlibs
+#[derive(Clone, Default)]
+pub struct LoaderInfo {
+    pub version: Option<String>,
+    // Known keys found in lindbergh.so or its docs,empty if none of them could be found
+    pub supported_keys: Vec<String>,
+}
+impl LoaderInfo {
+    pub fn detect() -> anyhow::Result<Self> {
+        let mut info = Self::from_bytes(&fs::read(LOADER_PATH)?);
+        for i in DOCS_PATHS {
+            if let Ok(buf) = fs::read_to_string(i) {
+                info.add_keys_from_docs(&buf);
+            }
+        }
+        Ok(info)
+    }
+    pub fn from_bytes(buf: &[u8]) -> Self {
+        let strings = printable_strings(buf);
+        let tokens = strings
+            .iter()
+            .flat_map(|s| s.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_')))
+            .collect::<HashSet<&str>>();
+        Self {
+            version: strings.iter().find_map(|s| find_version(s)),
+            // The linker may merge "HEIGHT" into the tail of "CUSTOM_CURSOR_HEIGHT",
+            // so a key also counts when another known key found ends with it
+            supported_keys: KNOWN_KEYS
+                .iter()
+                .filter(|k| {
+                    tokens.contains(*k)
+                        || KNOWN_KEYS.iter().any(|x| {
+                            x.strip_suffix(*k).is_some_and(|x| x.ends_with('_'))
+                                && tokens.contains(x)
+                        })
+                })
+                .map(|k| k.to_string())
+                .collect(),
+        }
+    }
+    // docs/lindbergh.conf lists most options commented out,like "# WIDTH 640"
+    pub fn add_keys_from_docs(&mut self, buf: &str) {
+        for i in buf.lines() {
+            let key = i
+                .trim_start_matches(|c: char| c == '#' || c.is_whitespace())
+                .split_whitespace()
+                .next()
+                .unwrap_or_default();
+            if KNOWN_KEYS.contains(&key) && !self.supported_keys.iter().any(|x| x == key) {
+                self.supported_keys.push(key.to_string());
+            }
+        }
+    }
+    pub fn supports(&self, key: &str) -> bool {
+        !KNOWN_KEYS.contains(&key)
+            || self.supported_keys.is_empty()
+            || self.supported_keys.iter().any(|x| x == key)
+    }
+    pub fn unsupported_keys(&self, conf: &str) -> Vec<String> {
+        crate::config::diff::parse_entries(conf)
+            .into_iter()
+            .map(|(k, _)| k)
+            .filter(|k| !self.supports(k))
+            .collect()
+    }
+}
+
+// Same as `strings(1)`
+fn printable_strings(buf: &[u8]) -> Vec<String> {
+    buf.split(|c| !(c.is_ascii_graphic() || *c == b' '))
+        .filter(|s| s.len() >= 4)
+        .map(|s| String::from_utf8_lossy(s).to_string())
+        .collect()
+}
+
+// Looks for something like "lindbergh-loader v2.1.0" or "Version: 2.1"
+fn find_version(s: &str) -> Option<String> {
+    let lower = s.to_ascii_lowercase();
+    if !lower.contains("lindbergh") && !lower.contains("version") {
+        return None;
+    }
+    s.split(|c: char| c.is_whitespace() || c == ':' || c == '(' || c == ')')
+        .map(|x| x.trim_start_matches(['v', 'V']))
+        .find(|x| {
+            let parts = x.split('.').collect::<Vec<&str>>();
+            parts.len() >= 2
+                && parts
+                    .iter()
+                    .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+        })
+        .map(|x| x.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_bytes() {
+        let so = b"\x7fELF\x02\x01\0\0lindbergh-loader v2.1.3\0WIDTH\0CUSTOM_CURSOR_HEIGHT\0FULLSCREEN\0\x01\x02XFREEPLAY\0";
+        let info = LoaderInfo::from_bytes(so);
+        assert_eq!(info.version.as_deref(), Some("2.1.3"));
+        assert!(info.supports("HEIGHT"));
+        assert!(info.supports("FULLSCREEN"));
+        assert!(!info.supports("EMULATE_CARDREADER"));
+        // part of a longer name isn't the key itself
+        assert!(!info.supports("FREEPLAY"));
+        // input keys are never checked
+        assert!(info.supports("PLAYER_1_BUTTON_1"));
+        assert_eq!(
+            info.unsupported_keys("# game.elf\nWIDTH 640\nOR2_IP 192.168.1.2\n"),
+            vec!["OR2_IP".to_string()]
+        );
+    }
+}
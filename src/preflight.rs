@@ -0,0 +1,227 @@
+use crate::config::LindberghConfig;
+use crate::discovery;
+use crate::link::{LinkKind, LinkPlan, Subnet};
+use network_interface::NetworkInterface;
+use network_interface::NetworkInterfaceConfig;
+use std::fs;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+const ECHO_TIMEOUT: Duration = Duration::from_millis(500);
+// From <net/if.h>
+const IFF_UP: u32 = 0x1;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CheckStatus {
+    Pass,
+    // Might still work,e.g. the peer doesn't run a launcher and drops pings
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+impl Check {
+    fn new(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+// Whether the NIC is administratively up and has a link
+pub fn nic_state(name: &str) -> anyhow::Result<(bool, String)> {
+    let dir = format!("/sys/class/net/{}", name);
+    let operstate = fs::read_to_string(format!("{}/operstate", dir))?;
+    let flags = fs::read_to_string(format!("{}/flags", dir))?;
+    let flags = u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16)?;
+    let operstate = operstate.trim().to_string();
+    // Loopback and some virtual NICs never report "up"
+    let up = flags & IFF_UP != 0 && (operstate == "up" || operstate == "unknown");
+    Ok((up, operstate))
+}
+
+fn ping(ip: Ipv4Addr) -> bool {
+    Command::new("ping")
+        .args(["-c", "1", "-W", "1", &ip.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|x| x.success())
+}
+
+// A launcher answering on `echo_port`,or failing that ICMP
+fn probe_peer(ip: Ipv4Addr, echo_port: u16) -> Check {
+    let name = format!("Peer {}", ip);
+    if discovery::echo(SocketAddr::from((ip, echo_port)), ECHO_TIMEOUT) {
+        Check::new(name, CheckStatus::Pass, "The launcher answered")
+    } else if ping(ip) {
+        Check::new(name, CheckStatus::Pass, "Answered ping")
+    } else {
+        Check::new(
+            name,
+            CheckStatus::Warn,
+            "No answer,is the cabinet on and its launcher open?",
+        )
+    }
+}
+
+/// Everything checked before launching a linked game
+/// NOTE: The UDP ports the games link on aren't documented anywhere,so they aren't checked
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Preflight {
+    pub checks: Vec<Check>,
+}
+impl Preflight {
+    // Peers are asked on `echo_port`,launchers listen on discovery::DISCOVERY_PORT
+    pub fn run(
+        kind: LinkKind,
+        config: &LindberghConfig,
+        interfaces: &[NetworkInterface],
+        echo_port: u16,
+    ) -> Self {
+        let mut checks = Vec::new();
+        let nic = &if config.nic_name.is_empty() {
+            kind.find_nic(config, interfaces).unwrap_or_default()
+        } else {
+            config.nic_name.clone()
+        };
+        let interface = interfaces.iter().find(|x| x.name == *nic);
+        match (interface, nic_state(nic)) {
+            (None, _) => checks.push(Check::new(
+                "Network card",
+                CheckStatus::Fail,
+                format!("\"{}\" doesn't exist", nic),
+            )),
+            (Some(_), Err(e)) => checks.push(Check::new(
+                "Network card",
+                CheckStatus::Warn,
+                format!("Unable to read the state of {}: {}", nic, e),
+            )),
+            (Some(_), Ok((true, _))) => {
+                checks.push(Check::new("Network card", CheckStatus::Pass, nic.as_str()))
+            }
+            (Some(_), Ok((false, state))) => checks.push(Check::new(
+                "Network card",
+                CheckStatus::Fail,
+                format!("{} is {},check the cable", nic, state),
+            )),
+        }
+        let Some(subnet) = interface.and_then(Subnet::of) else {
+            checks.push(Check::new(
+                "IP address",
+                CheckStatus::Fail,
+                format!("{} has no IPv4 address", nic),
+            ));
+            return Self { checks };
+        };
+        let seats = kind.seats(config);
+        let plan = LinkPlan::parse(kind, subnet, &seats);
+        match &plan {
+            Err(e) => checks.push(Check::new("IP address", CheckStatus::Fail, e.to_string())),
+            Ok(x) => match x.this_seat() {
+                Some(seat) => checks.push(Check::new(
+                    "IP address",
+                    CheckStatus::Pass,
+                    format!("{} is seat {}", subnet, seat + 1),
+                )),
+                None => checks.push(Check::new(
+                    "IP address",
+                    CheckStatus::Fail,
+                    format!(
+                        "{} holds {},but the config expects one of {}",
+                        nic,
+                        subnet.ip,
+                        seats.join(",")
+                    ),
+                )),
+            },
+        }
+        // Outrun 2 only knows its own IP,it finds the other cabinets by itself
+        let peers: Vec<Ipv4Addr> = plan
+            .map(|x| x.seats.into_iter().filter(|ip| *ip != subnet.ip).collect())
+            .unwrap_or_default();
+        let probes: Vec<Check> = thread::scope(|s| {
+            let handles: Vec<_> = peers
+                .iter()
+                .map(|ip| s.spawn(move || probe_peer(*ip, echo_port)))
+                .collect();
+            handles.into_iter().map(|x| x.join().unwrap()).collect()
+        });
+        checks.extend(probes);
+        Self { checks }
+    }
+    // The peers take up to a second to answer,so the UI runs the checks on another thread
+    pub fn run_in_background(
+        kind: LinkKind,
+        config: LindberghConfig,
+        echo_port: u16,
+    ) -> Receiver<Self> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let interfaces = NetworkInterface::show().unwrap_or_default();
+            let _ = sender.send(Self::run(kind, &config, &interfaces, echo_port));
+        });
+        receiver
+    }
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|x| x.status == CheckStatus::Pass)
+    }
+    pub fn failed(&self) -> bool {
+        self.checks.iter().any(|x| x.status == CheckStatus::Fail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::Discovery;
+
+    #[test]
+    fn test_loopback_preflight() {
+        // Every 127.x.x.x address reaches a launcher listening on all of them
+        let launcher = Discovery::start(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))).unwrap();
+        let interfaces = NetworkInterface::show().unwrap();
+        let mut config = LindberghConfig {
+            nic_name: "lo".into(),
+            id45_ip_seat: ["127.0.0.2".into(), "127.0.0.1".into()],
+            ..Default::default()
+        };
+        let preflight = Preflight::run(
+            LinkKind::InitialD,
+            &config,
+            &interfaces,
+            launcher.local_addr.port(),
+        );
+        assert_eq!(preflight.checks[0].status, CheckStatus::Pass);
+        assert_eq!(preflight.checks[1].detail, "127.0.0.1/8 is seat 2");
+        assert_eq!(preflight.checks[2].name, "Peer 127.0.0.2");
+        assert_eq!(preflight.checks[2].status, CheckStatus::Pass);
+
+        // Configs saved without NIC_NAME use the NIC holding a seat IP
+        config.nic_name = String::new();
+        let preflight = Preflight::run(
+            LinkKind::InitialD,
+            &config,
+            &interfaces,
+            launcher.local_addr.port(),
+        );
+        assert_eq!(preflight.checks[0].detail, "lo");
+        assert_eq!(preflight.checks[1].status, CheckStatus::Pass);
+
+        config.id45_ip_seat = ["127.0.0.2".into(), "127.0.0.3".into()];
+        config.nic_name = "no-such-nic".into();
+        let preflight = Preflight::run(LinkKind::InitialD, &config, &interfaces, 0);
+        assert!(preflight.failed());
+        assert_eq!(preflight.checks.len(), 2);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::config::LindberghConfig;
+use crate::games::GameTitle;
+use anyhow::anyhow;
+
+/// Width and height in pixels
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+impl Resolution {
+    pub const fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+    // WIDTHxHEIGHT
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let (width, height) = s
+            .trim()
+            .split_once(['x', 'X'])
+            .ok_or(anyhow!("\"{}\" is not WIDTHxHEIGHT", s))?;
+        Ok(Self {
+            width: width.trim().parse()?,
+            height: height.trim().parse()?,
+        })
+    }
+    pub fn ratio(&self) -> f64 {
+        self.width as f64 / self.height.max(1) as f64
+    }
+    // The common name of the aspect ratio,or the reduced fraction
+    pub fn aspect(&self) -> String {
+        // 1360x768 and 1366x768 are sold as 16:9 too
+        if let Some((name, _)) = ASPECTS
+            .iter()
+            .find(|(_, x)| (self.ratio() / x - 1.0).abs() < 0.01)
+        {
+            return name.to_string();
+        }
+        let (mut a, mut b) = (self.width, self.height);
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        let gcd = a.max(1);
+        format!("{}:{}", self.width / gcd, self.height / gcd)
+    }
+    pub fn fits_in(&self, other: Resolution) -> bool {
+        self.width <= other.width && self.height <= other.height
+    }
+    // Largest size with this aspect ratio inside `area`
+    pub fn fit(&self, area: Resolution) -> Resolution {
+        if area.ratio() > self.ratio() {
+            Resolution::new(
+                (area.height as f64 * self.ratio()).round() as u32,
+                area.height,
+            )
+        } else {
+            Resolution::new(
+                area.width,
+                (area.width as f64 / self.ratio()).round() as u32,
+            )
+        }
+    }
+}
+impl From<(u32, u32)> for Resolution {
+    fn from(value: (u32, u32)) -> Self {
+        Self::new(value.0, value.1)
+    }
+}
+impl From<Resolution> for (u32, u32) {
+    fn from(value: Resolution) -> Self {
+        (value.width, value.height)
+    }
+}
+impl std::fmt::Display for Resolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+const ASPECTS: [(&str, f64); 5] = [
+    ("4:3", 4.0 / 3.0),
+    ("5:4", 5.0 / 4.0),
+    ("5:3", 5.0 / 3.0),
+    ("16:9", 16.0 / 9.0),
+    ("16:10", 16.0 / 10.0),
+];
+
+// (group,sizes) shown in the preset list
+pub const PRESETS: [(&str, &[Resolution]); 4] = [
+    (
+        "4:3",
+        &[
+            Resolution::new(640, 480),
+            Resolution::new(800, 600),
+            Resolution::new(1024, 768),
+            Resolution::new(1280, 960),
+            Resolution::new(1600, 1200),
+        ],
+    ),
+    (
+        "16:9",
+        &[
+            Resolution::new(1280, 720),
+            Resolution::new(1360, 768),
+            Resolution::new(1366, 768),
+            Resolution::new(1600, 900),
+            Resolution::new(1920, 1080),
+            Resolution::new(2560, 1440),
+            Resolution::new(3840, 2160),
+        ],
+    ),
+    (
+        "16:10",
+        &[
+            Resolution::new(1280, 800),
+            Resolution::new(1440, 900),
+            Resolution::new(1680, 1050),
+            Resolution::new(1920, 1200),
+            Resolution::new(2560, 1600),
+        ],
+    ),
+    // Monitors found in Lindbergh cabinets
+    (
+        "Cabinet",
+        &[
+            Resolution::new(800, 480),
+            Resolution::new(1024, 600),
+            Resolution::new(1280, 768),
+            Resolution::new(1280, 1024),
+        ],
+    ),
+];
+
+// What the game renders at before the loader scales it to the window
+pub fn native_resolution(title: &GameTitle) -> Resolution {
+    match title {
+        GameTitle::Outrun_2_SP_SDX => Resolution::new(800, 480),
+        GameTitle::InitialD_4
+        | GameTitle::InitalD_4_Export
+        | GameTitle::InitialD_5_Japan
+        | GameTitle::InitalD_5_Export_Ver_2
+        | GameTitle::InitalD_5_Export_Ver_4
+        | GameTitle::Lets_Go_Jungle
+        | GameTitle::Lets_Go_Jungle_Special
+        | GameTitle::Virtua_Tennis_3
+        | GameTitle::Sega_Race_TV
+        | GameTitle::R_Tuned
+        | GameTitle::Hummer_Extreme
+        | GameTitle::Hummer_Extreme_MDX => Resolution::new(1360, 768),
+        GameTitle::The_House_Of_The_Dead_4
+        | GameTitle::The_House_Of_The_Dead_4_Special
+        | GameTitle::The_House_Of_The_Dead_EX
+        | GameTitle::Harley_Davidson
+        | GameTitle::Rambo
+        | GameTitle::Rambo_China
+        | GameTitle::Too_Spicy
+        | GameTitle::Virtua_Fighter_5_R
+        | GameTitle::Virtua_Fighter_5_Final_Showdown
+        | GameTitle::Taisen_Mahjong_4
+        | GameTitle::Taisen_Mahjong_4_Evolution => Resolution::new(1280, 768),
+        _ => Resolution::new(640, 480),
+    }
+}
+
+// Whole multiples of `native` that fit on `screen`,the first two when the screen is unknown
+pub fn integer_scales(native: Resolution, screen: Option<Resolution>) -> Vec<(u32, Resolution)> {
+    (1..=8)
+        .map(|x| (x, Resolution::new(native.width * x, native.height * x)))
+        .take_while(|(x, size)| screen.map_or(*x <= 2, |s| size.fits_in(s)))
+        .collect()
+}
+
+// Part of the window left to the game once the borders are drawn
+pub fn picture_area(config: &LindberghConfig) -> Option<Resolution> {
+    let window = Resolution::from(config.window_size);
+    if !config.border_enabled {
+        return Some(window);
+    }
+    // Both borders are drawn on every side,sized by the window's height
+    let border =
+        window.height * (config.white_border_percentage + config.black_border_percentage) / 100;
+    Some(Resolution::new(
+        window.width.checked_sub(border * 2).filter(|x| *x > 0)?,
+        window.height.checked_sub(border * 2).filter(|x| *x > 0)?,
+    ))
+}
+
+// Why the window size can't be used,if it can't
+pub fn window_error(config: &LindberghConfig) -> Option<String> {
+    if config.window_size.0 == 0 || config.window_size.1 == 0 {
+        Some("Width and height can't be 0".into())
+    } else if picture_area(config).is_none() {
+        Some("The borders cover the whole window,make them thinner".into())
+    } else {
+        None
+    }
+}
+
+/// Checks the window size against the game,the screen and the picture settings
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SizeCheck {
+    // The config can't be saved
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    // Size and scale of the game's picture in the window
+    pub picture: Option<(Resolution, f64)>,
+}
+impl SizeCheck {
+    pub fn run(config: &LindberghConfig, native: Resolution, screen: Option<Resolution>) -> Self {
+        let mut check = Self::default();
+        let window = Resolution::from(config.window_size);
+        if let Some(e) = window_error(config) {
+            check.errors.push(e);
+            return check;
+        }
+        let area = picture_area(config).unwrap();
+        if let Some(screen) = screen
+            && !window.fits_in(screen)
+        {
+            check
+                .warnings
+                .push(format!("{} is larger than the screen ({})", window, screen));
+        }
+        let picture = if config.keep_aspect_ratio {
+            native.fit(area)
+        } else {
+            area
+        };
+        if !config.keep_aspect_ratio && picture.aspect() != native.aspect() {
+            check.warnings.push(format!(
+                "The game's {} picture is stretched to {},turn on \"Keep aspect ratio\" to avoid it",
+                native.aspect(),
+                picture.aspect()
+            ));
+        }
+        let scale = picture.height as f64 / native.height as f64;
+        let whole = picture.width.is_multiple_of(native.width)
+            && picture.height.is_multiple_of(native.height)
+            && picture.width / native.width == picture.height / native.height;
+        if !whole {
+            check.warnings.push(format!(
+                "{:.2}x is not a whole scale of {},pixels are scaled unevenly",
+                scale, native
+            ));
+        }
+        check.picture = Some((picture, scale));
+        check
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_check() {
+        assert_eq!(Resolution::parse("1360 x 768").unwrap().aspect(), "16:9");
+        assert_eq!(Resolution::new(800, 480).aspect(), "5:3");
+        assert_eq!(Resolution::new(1000, 700).aspect(), "10:7");
+        assert!(Resolution::parse("1024").is_err());
+        let native = native_resolution(&GameTitle::Virtua_Fighter_5);
+        assert_eq!(
+            integer_scales(native, Some(Resolution::new(1920, 1080))),
+            vec![(1, native), (2, Resolution::new(1280, 960))]
+        );
+
+        let mut config = LindberghConfig {
+            window_size: (1920, 1080),
+            ..Default::default()
+        };
+        let check = SizeCheck::run(&config, native, Some(Resolution::new(1920, 1080)));
+        assert!(check.errors.is_empty());
+        assert_eq!(check.warnings.len(), 2);
+        config.keep_aspect_ratio = true;
+        let check = SizeCheck::run(&config, native, None);
+        assert_eq!(check.picture, Some((Resolution::new(1440, 1080), 2.25)));
+        config.window_size = (1280, 960);
+        let check = SizeCheck::run(&config, native, None);
+        assert!(check.warnings.is_empty());
+        config.border_enabled = true;
+        config.white_border_percentage = 30;
+        config.black_border_percentage = 20;
+        assert_eq!(SizeCheck::run(&config, native, None).errors.len(), 1);
+    }
+}
@@ -83,7 +83,13 @@ pub fn monitor_game(path: &str, child: &mut Child) -> anyhow::Result<Option<Exit
         Ok(None)
     }
 }
-pub fn run_game(path: &str, test_mode: bool, current_game: &GameTitle) -> anyhow::Result<Child> {
+// `env` is added to the game's environment,e.g. the display it should open on
+pub fn run_game(
+    path: &str,
+    test_mode: bool,
+    current_game: &GameTitle,
+    env: &[(String, String)],
+) -> anyhow::Result<Child> {
     check_files()?;
     copy_files(path)?;
     let mut path = PathBuf::from(path);
@@ -95,7 +101,7 @@ pub fn run_game(path: &str, test_mode: bool, current_game: &GameTitle) -> anyhow
         .to_string();
     path.pop();
     if test_mode && get_test(&fname).is_some() {
-        return run_game(get_test(&fname).unwrap().as_str(), false, current_game);
+        return run_game(get_test(&fname).unwrap().as_str(), false, current_game, env);
     }
     let child = Command::new(format!("{}/{}", path.display(), fname))
         .env(
@@ -107,6 +113,7 @@ pub fn run_game(path: &str, test_mode: bool, current_game: &GameTitle) -> anyhow
             },
         )
         .env("LD_PRELOAD", "lindbergh.so")
+        .envs(env.iter().cloned())
         .env(
             "LINDBERGH_CONFIG_PATH",
             format!(
@@ -0,0 +1,450 @@
+use anyhow::anyhow;
+use chrono::Local;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+// Serial ports lindbergh-loader can talk to,ttyS covers the onboard ones
+const TTY_PREFIXES: [&str; 3] = ["ttyS", "ttyUSB", "ttyACM"];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UsbInfo {
+    pub vendor_id: String,
+    pub product_id: String,
+    pub manufacturer: String,
+    pub product: String,
+}
+impl std::fmt::Display for UsbInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = format!("{} {}", self.manufacturer, self.product);
+        write!(
+            f,
+            "{} ({}:{})",
+            name.trim(),
+            self.vendor_id,
+            self.product_id
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SerialPort {
+    pub path: PathBuf,
+    // /dev/serial/by-id links,they survive replugging unlike ttyUSBn
+    pub by_id: Vec<PathBuf>,
+    pub usb: Option<UsbInfo>,
+    // access_problem() when the ports were listed
+    pub problem: Option<String>,
+}
+impl SerialPort {
+    pub fn description(&self) -> String {
+        match &self.usb {
+            Some(usb) => usb.to_string(),
+            None => "Onboard serial port".to_string(),
+        }
+    }
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.path).chain(self.by_id.iter())
+    }
+}
+
+fn read_attr(dir: &Path, name: &str) -> String {
+    fs::read_to_string(dir.join(name))
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+// The tty sits below the USB interface,the device with the ids is a few levels up
+fn usb_info(node: &str) -> Option<UsbInfo> {
+    let dev = fs::canonicalize(format!("/sys/class/tty/{}/device", node)).ok()?;
+    let dir = dev.ancestors().find(|x| x.join("idVendor").exists())?;
+    Some(UsbInfo {
+        vendor_id: read_attr(dir, "idVendor"),
+        product_id: read_attr(dir, "idProduct"),
+        manufacturer: read_attr(dir, "manufacturer"),
+        product: read_attr(dir, "product"),
+    })
+}
+
+pub fn list_ports() -> anyhow::Result<Vec<SerialPort>> {
+    let by_id: Vec<(PathBuf, PathBuf)> = fs::read_dir("/dev/serial/by-id")
+        .map(|x| {
+            x.flatten()
+                .filter_map(|x| Some((fs::canonicalize(x.path()).ok()?, x.path())))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut ports = Vec::new();
+    for i in fs::read_dir("/sys/class/tty")? {
+        let i = i?;
+        let node = i.file_name().to_string_lossy().to_string();
+        if !TTY_PREFIXES.iter().any(|x| {
+            node.strip_prefix(x)
+                .is_some_and(|n| n.parse::<u32>().is_ok())
+        }) {
+            continue;
+        }
+        // The kernel registers ttyS0~31 whether or not a UART is behind them
+        if node.starts_with("ttyS") && read_attr(&i.path(), "type") == "0" {
+            continue;
+        }
+        let path = PathBuf::from(format!("/dev/{}", node));
+        ports.push(SerialPort {
+            by_id: by_id
+                .iter()
+                .filter(|x| x.0 == path)
+                .map(|x| x.1.clone())
+                .collect(),
+            usb: usb_info(&node),
+            problem: access_problem(&path),
+            path,
+        });
+    }
+    ports.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(ports)
+}
+
+// Real uid,and the primary group followed by the supplementary ones from /proc/self/status
+fn parse_ids(status: &str) -> (u32, Vec<u32>) {
+    let field = |name: &str| {
+        status
+            .lines()
+            .find_map(|x| x.strip_prefix(name))
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter_map(|x| x.parse::<u32>().ok())
+            .collect::<Vec<_>>()
+    };
+    let mut groups = field("Groups:");
+    if let Some(gid) = field("Gid:").first()
+        && !groups.contains(gid)
+    {
+        groups.insert(0, *gid);
+    }
+    (field("Uid:").first().copied().unwrap_or(u32::MAX), groups)
+}
+
+// (name,members) of a group in /etc/group
+fn parse_group(group_file: &str, gid: u32) -> Option<(String, Vec<String>)> {
+    group_file.lines().find_map(|x| {
+        let r: Vec<&str> = x.split(':').collect();
+        if r.len() < 4 || r[2].parse::<u32>().ok()? != gid {
+            return None;
+        }
+        Some((
+            r[0].to_string(),
+            r[3].split(',')
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_string())
+                .collect(),
+        ))
+    })
+}
+
+fn can_read_write(mode: u32, owner: u32, group: u32, uid: u32, groups: &[u32]) -> bool {
+    if uid == 0 {
+        return true;
+    }
+    let bits = if owner == uid {
+        mode >> 6
+    } else if groups.contains(&group) {
+        mode >> 3
+    } else {
+        mode
+    };
+    bits & 0o6 == 0o6
+}
+
+/// Why the loader won't be able to open the port,None if it can
+pub fn access_problem(path: &Path) -> Option<String> {
+    let meta = match fs::metadata(path) {
+        Ok(m) => m,
+        Err(e) => return Some(format!("{} is not available: {}", path.display(), e)),
+    };
+    let (uid, groups) = parse_ids(&fs::read_to_string("/proc/self/status").unwrap_or_default());
+    if can_read_write(meta.mode(), meta.uid(), meta.gid(), uid, &groups) {
+        return None;
+    }
+    // Only worth suggesting the group when it actually grants access
+    if meta.mode() & 0o060 != 0o060 {
+        return Some(format!("No read/write permission on {}", path.display()));
+    }
+    let group_file = fs::read_to_string("/etc/group").unwrap_or_default();
+    let Some((name, members)) = parse_group(&group_file, meta.gid()) else {
+        return Some(format!("No read/write permission on {}", path.display()));
+    };
+    let user = std::env::var("USER").unwrap_or_default();
+    if !user.is_empty() && members.contains(&user) {
+        Some(format!(
+            "You were added to group \"{}\",log out and back in to access {}",
+            name,
+            path.display()
+        ))
+    } else {
+        Some(format!(
+            "Join group \"{}\" to access {}:\nsudo usermod -aG {} $USER",
+            name,
+            path.display(),
+            name
+        ))
+    }
+}
+
+pub const BAUD_RATES: [u32; 5] = [9600, 19200, 38400, 57600, 115200];
+
+fn baud_constant(baud: u32) -> anyhow::Result<libc::speed_t> {
+    match baud {
+        9600 => Ok(libc::B9600),
+        19200 => Ok(libc::B19200),
+        38400 => Ok(libc::B38400),
+        57600 => Ok(libc::B57600),
+        115200 => Ok(libc::B115200),
+        _ => Err(anyhow!("Unsupported baud rate {}", baud)),
+    }
+}
+
+/// Opens a serial port raw,8N1 at the given baud rate
+pub fn open_port(path: &Path, baud: u32) -> anyhow::Result<File> {
+    let speed = baud_constant(baud)?;
+    let port = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(path)
+        .map_err(|e| anyhow!("Unable to open {}: {}", path.display(), e))?;
+    let fd = port.as_raw_fd();
+    // SAFETY: fd stays open for the whole block and tio is fully initialised by tcgetattr
+    unsafe {
+        let mut tio: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(fd, &mut tio) != 0 {
+            return Err(anyhow!(
+                "{} is not a serial port: {}",
+                path.display(),
+                io::Error::last_os_error()
+            ));
+        }
+        libc::cfmakeraw(&mut tio);
+        libc::cfsetspeed(&mut tio, speed);
+        tio.c_cflag |= libc::CLOCAL | libc::CREAD;
+        // A read gives up after half a second of silence
+        tio.c_cc[libc::VMIN] = 0;
+        tio.c_cc[libc::VTIME] = 5;
+        if libc::tcsetattr(fd, libc::TCSANOW, &tio) != 0 {
+            return Err(anyhow!(
+                "Unable to configure {}: {}",
+                path.display(),
+                io::Error::last_os_error()
+            ));
+        }
+        libc::tcflush(fd, libc::TCIOFLUSH);
+    }
+    Ok(port)
+}
+
+// Drive and motion boards are usually set up at this speed,the console lets it be changed
+pub const BOARD_BAUD: u32 = 38400;
+// Older entries are dropped so a chatty board doesn't grow the log forever
+const MAX_LOG: usize = 1000;
+
+/// The board a test console talks to
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BoardKind {
+    Drive,
+    Motion,
+    Ride,
+}
+impl std::fmt::Display for BoardKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Drive => write!(f, "Drive Board"),
+            Self::Motion => write!(f, "Motion Board"),
+            Self::Ride => write!(f, "Ride Board"),
+        }
+    }
+}
+
+// "ff 0a" or "FF0A"
+pub fn parse_hex(text: &str) -> anyhow::Result<Vec<u8>> {
+    let digits: String = text.split_whitespace().collect();
+    if let Some(c) = digits.chars().find(|x| !x.is_ascii_hexdigit()) {
+        return Err(anyhow!("\"{}\" is not a hex digit", c));
+    }
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return Err(anyhow!("Enter the bytes as pairs of hex digits"));
+    }
+    // Only ASCII is left,every pair is two bytes
+    Ok(digits
+        .as_bytes()
+        .chunks(2)
+        .map(|x| u8::from_str_radix(std::str::from_utf8(x).unwrap(), 16).unwrap())
+        .collect())
+}
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|x| format!("{:02X}", x))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LogEntry {
+    pub time: String,
+    pub direction: Direction,
+    pub bytes: Vec<u8>,
+}
+impl std::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let arrow = match self.direction {
+            Direction::Sent => "->",
+            Direction::Received => "<-",
+        };
+        write!(f, "{} {} {}", self.time, arrow, to_hex(&self.bytes))
+    }
+}
+
+/// Raw access to a serial port,everything sent and received is logged
+pub struct SerialConsole {
+    pub path: PathBuf,
+    pub baud: u32,
+    port: File,
+    receiver: Receiver<Vec<u8>>,
+    pub log: Vec<LogEntry>,
+}
+impl SerialConsole {
+    pub fn open(path: &Path, baud: u32) -> anyhow::Result<Self> {
+        let port = open_port(path, baud)?;
+        let mut reader = port.try_clone()?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buf = [0u8; 256];
+            loop {
+                let n = match reader.read(&mut buf) {
+                    Err(_) => break,
+                    Ok(n) => n,
+                };
+                // Reads time out,so this also notices the console being dropped
+                if sender.send(buf[..n].to_vec()).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Self {
+            path: path.to_path_buf(),
+            baud,
+            port,
+            receiver,
+            log: vec![],
+        })
+    }
+    fn push_log(&mut self, direction: Direction, bytes: Vec<u8>) {
+        self.log.push(LogEntry {
+            time: Local::now().format("%H:%M:%S%.3f").to_string(),
+            direction,
+            bytes,
+        });
+        if self.log.len() > MAX_LOG {
+            self.log.drain(..self.log.len() - MAX_LOG);
+        }
+    }
+    pub fn send(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        self.port.write_all(bytes)?;
+        self.push_log(Direction::Sent, bytes.to_vec());
+        Ok(())
+    }
+    // Whatever arrived since the last update becomes one log entry
+    pub fn update(&mut self) {
+        let bytes: Vec<u8> = self.receiver.try_iter().flatten().collect();
+        if !bytes.is_empty() {
+            self.push_log(Direction::Received, bytes);
+        }
+    }
+}
+
+// Master side of a pseudo-terminal,and the path of its serial port end
+#[cfg(test)]
+pub(crate) fn open_pty() -> (File, PathBuf) {
+    use std::ffi::CStr;
+    use std::os::fd::FromRawFd;
+    // SAFETY: plain libc calls,the fd is handed over to the File
+    unsafe {
+        let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        assert!(fd >= 0);
+        assert_eq!(libc::grantpt(fd), 0);
+        assert_eq!(libc::unlockpt(fd), 0);
+        let mut name = [0 as libc::c_char; 64];
+        assert_eq!(libc::ptsname_r(fd, name.as_mut_ptr(), name.len()), 0);
+        let path = PathBuf::from(CStr::from_ptr(name.as_ptr()).to_str().unwrap());
+        (File::from_raw_fd(fd), path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access() {
+        let status = "Name:\tcat\nUid:\t1000\t1000\t1000\t1000\nGid:\t1000\t1000\t1000\t1000\nGroups:\t4 24 1000 \n";
+        let (uid, groups) = parse_ids(status);
+        assert_eq!(uid, 1000);
+        assert_eq!(groups, vec![4, 24, 1000]);
+        // The primary group isn't always listed in Groups
+        let status = "Uid:\t1000\t1000\t1000\t1000\nGid:\t20\t20\t20\t20\nGroups:\t4 24 \n";
+        assert_eq!(parse_ids(status).1, vec![20, 4, 24]);
+        let group_file = "root:x:0:\nuucp:x:14:\ndialout:x:20:alice,bob\n";
+        assert_eq!(
+            parse_group(group_file, 20),
+            Some(("dialout".to_string(), vec!["alice".into(), "bob".into()]))
+        );
+        assert_eq!(parse_group(group_file, 14).unwrap().1, Vec::<String>::new());
+        // crw-rw---- root:dialout
+        assert!(!can_read_write(0o20660, 0, 20, uid, &groups));
+        assert!(can_read_write(0o20660, 0, 20, uid, &[20]));
+        assert!(can_read_write(0o20660, 0, 20, 0, &[]));
+        assert!(can_read_write(0o20600, 1000, 20, uid, &[]));
+        assert!(!can_read_write(0o20640, 0, 20, uid, &[20]));
+    }
+
+    #[test]
+    fn test_console() {
+        assert_eq!(parse_hex("ff 0a").unwrap(), vec![0xFF, 0x0A]);
+        assert_eq!(parse_hex("FF0A").unwrap(), vec![0xFF, 0x0A]);
+        assert!(parse_hex("F").is_err());
+        assert!(parse_hex("GG").is_err());
+        assert!(parse_hex("aéa").is_err());
+        assert!(open_port(Path::new("/dev/null"), 1234).is_err());
+
+        let (mut master, path) = open_pty();
+        let mut console = SerialConsole::open(&path, BOARD_BAUD).unwrap();
+        console.send(&[0xFF, 0x10]).unwrap();
+        let mut buf = [0u8; 2];
+        master.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0xFF, 0x10]);
+        master.write_all(&[0x00, 0x01]).unwrap();
+        let start = std::time::Instant::now();
+        while console.log.len() < 2 {
+            assert!(start.elapsed() < std::time::Duration::from_secs(5));
+            thread::sleep(std::time::Duration::from_millis(10));
+            console.update();
+        }
+        assert_eq!(console.log[0].direction, Direction::Sent);
+        assert_eq!(console.log[1].bytes, vec![0x00, 0x01]);
+        assert!(console.log[1].to_string().ends_with("<- 00 01"));
+        for _ in 0..MAX_LOG {
+            console.push_log(Direction::Received, vec![0x02]);
+        }
+        assert_eq!(console.log.len(), MAX_LOG);
+        assert_eq!(console.log[0].bytes, vec![0x02]);
+    }
+}
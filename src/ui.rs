@@ -1,21 +1,53 @@
+use crate::cards::{self, CardFile};
+use crate::config::diff::{ConfChange, diff_conf};
 use crate::config::{
-    GameRegion, GpuType, Keymap, LindberghColor, LindberghConfig, executable_path,
+    BindingSlot, GameRegion, GpuType, Keymap, KeymapField, LindberghColor, LindberghConfig,
+    executable_path, presets,
 };
+use crate::discovery::{self, Discovery};
+use crate::display::{self, DisplayTarget, Output};
+use crate::evdev::{
+    self, AXIS_CAPTURE_THRESHOLD, AxisReadout, AxisState, EventCapture, InputDevice, InputMonitor,
+};
+use crate::gamepad::{self, GamepadNav, NavAction};
 use crate::games::{GameData, GameTitle, GameType};
+use crate::gpu::GpuReport;
+use crate::history::{EditHistory, revisions};
+use crate::input_template::InputTemplate;
+use crate::jvs::{self, JvsMonitor};
+use crate::keycode::{KEYCODE_ALT, KEYCODE_CONTROL, KEYCODE_SHIFT, SdlKey};
+use crate::link::{LinkKind, LinkPlan, Subnet};
+use crate::loader_info::{KNOWN_KEYS, LoaderInfo};
+use crate::preflight::{CheckStatus, Preflight};
+use crate::resolution::{self, Resolution, SizeCheck};
 use crate::runner::{monitor_game, run_game};
+use crate::serial::{self, BoardKind, Direction, SerialConsole, SerialPort};
 use anyhow::{Ok, anyhow};
-use eframe::egui::{self, Color32, Key, Modal, RichText};
+use eframe::egui::{self, Color32, Key, KeyboardShortcut, Modal, Modifiers, RichText};
 use network_interface::NetworkInterface;
 use network_interface::NetworkInterfaceConfig;
 use rfd::FileDialog;
 use std::fs::{self, remove_file};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
 use std::time::Duration;
 enum AppState {
     MainPage,
     ConfigureMapping,
+    ConfigureGun,
     ConfigureGame,
+    JvsDiagnostics,
+    SerialConsole,
+    CardManager,
+    LinkWizard,
     NewGame,
 }
+// What a key press on an evdev device is going to be bound to
+#[derive(PartialEq, Clone, Copy)]
+enum CaptureTarget {
+    Key(usize, KeymapField),
+}
 #[derive(PartialEq, Clone, Eq)]
 enum ModalStatus {
     Error,
@@ -40,6 +72,29 @@ pub struct SharedState {
     pub temp_config: LindberghConfig,
     pub first_run: [bool; 4],
     pub temp_interface: Vec<NetworkInterface>,
+    // Some(..) while the change preview of Save is shown
+    pub pending_changes: Option<Vec<ConfChange>>,
+    pub leave_warning: bool,
+    pub history: EditHistory,
+    // Some(..) while the saved revisions list is shown
+    pub revisions: Option<Vec<PathBuf>>,
+    pub evdev_devices: Vec<InputDevice>,
+    pub evdev_device: Option<usize>,
+    pub serial_ports: Vec<SerialPort>,
+    pub presets: Vec<String>,
+    pub selected_preset: Option<String>,
+    // Name typed for "Save as Preset"
+    pub preset_name: String,
+    pub cards: Vec<CardFile>,
+    // Name typed for a new card
+    pub card_name: String,
+    // Some((card,new name)) while a card is being renamed
+    pub card_rename: Option<(String, String)>,
+    // Seat IPs typed into the link wizard
+    pub link_seats: Vec<String>,
+    pub displays: Vec<Output>,
+    // Why no displays were found,e.g. xrandr is missing
+    pub display_error: Option<String>,
 }
 impl Default for SharedState {
     fn default() -> Self {
@@ -49,19 +104,36 @@ impl Default for SharedState {
             temp_config: LindberghConfig::default(),
             first_run: [true; 4],
             temp_interface: vec![],
+            pending_changes: None,
+            leave_warning: false,
+            history: EditHistory::default(),
+            revisions: None,
+            evdev_devices: vec![],
+            evdev_device: None,
+            serial_ports: vec![],
+            presets: vec![],
+            selected_preset: None,
+            preset_name: String::new(),
+            cards: vec![],
+            card_name: String::new(),
+            card_rename: None,
+            link_seats: vec![],
+            displays: vec![],
+            display_error: None,
         }
     }
 }
 impl SharedState {
-    pub fn assign_conf(&self, lconf: &mut LindberghConfig) -> anyhow::Result<()> {
+    pub fn assign_conf(
+        &self,
+        lconf: &mut LindberghConfig,
+        current_title: &GameTitle,
+    ) -> anyhow::Result<()> {
         if lconf.exe_path.is_empty() {
             return Err(anyhow!("Unspecified executable path"));
         }
-        if !self.shared_text[0].is_empty() && !self.shared_text[1].is_empty() {
-            lconf.window_size = (
-                self.shared_text[0].parse::<u32>()?,
-                self.shared_text[1].parse::<u32>()?,
-            );
+        if let Some(e) = resolution::window_error(lconf) {
+            return Err(anyhow!(e));
         }
         if lconf.enable_fps_limiter {
             lconf.limit_fps_target = self.shared_text[2].parse()?;
@@ -73,8 +145,27 @@ impl SharedState {
         if lconf.custom_cursor_height == 0 || lconf.custom_cursor_width == 0 {
             return Err(anyhow!("Custom cursor width or height cannot be 0"));
         }
+        // Card files are only written for games with a card reader
+        if current_title.uses_card_reader()
+            && lconf.emulate_cardreader
+            && lconf.card_file[0] == lconf.card_file[1]
+        {
+            return Err(anyhow!("Both players cannot use the same card file"));
+        }
         Ok(())
     }
+    // The numbers typed so far,so they can be undone like any other edit
+    pub fn apply_text(&self, lconf: &mut LindberghConfig) {
+        if let Result::Ok(x) = self.shared_text[2].parse() {
+            lconf.limit_fps_target = x;
+        }
+        if let Result::Ok(x) = self.shared_text[3].parse() {
+            lconf.custom_cursor_width = x;
+        }
+        if let Result::Ok(x) = self.shared_text[4].parse() {
+            lconf.custom_cursor_height = x;
+        }
+    }
 }
 pub struct LoaderApp {
     app_state: AppState,
@@ -84,6 +175,37 @@ pub struct LoaderApp {
     shared_state: SharedState,
     current_game: GameTitle,
     current_process: Option<std::process::Child>,
+    loader_info: Option<LoaderInfo>,
+    // Some(..) while waiting for a key press on an evdev device
+    evdev_capture: Option<(CaptureTarget, EventCapture)>,
+    // Live readout of the capture device,for calibrating analogue axes
+    axis_monitor: Option<(EventCapture, AxisReadout)>,
+    // Analogue channel waiting for an axis to be moved
+    axis_capture: Option<usize>,
+    gamepad_nav: Option<GamepadNav>,
+    // The input tester panel is shown
+    input_test: bool,
+    input_monitor: Option<InputMonitor>,
+    // Some(..) while the JVS diagnostics page is open
+    jvs_monitor: Option<JvsMonitor>,
+    // Some(..) while the serial test console is open
+    serial_console: Option<(BoardKind, SerialConsole)>,
+    // Hex typed into the console
+    console_input: String,
+    // Some(..) while the link wizard or the preflight dialog is open,and while a linked game runs
+    discovery: Option<Discovery>,
+    // Some((test mode,results)) while the preflight dialog is open
+    preflight: Option<(bool, Preflight)>,
+    // Some((test mode,results)) while the preflight checks run
+    preflight_check: Option<(bool, Receiver<Preflight>)>,
+    // Detected once,glxinfo takes a while
+    gpu: Option<GpuReport>,
+    // Some(..) while the GPU is being detected
+    gpu_detection: Option<Receiver<GpuReport>>,
+    // Some((test mode,game)) while asking to launch a game that doesn't work on AMD/ATI
+    ati_warning: Option<(bool, GameTitle)>,
+    // (game,config,keys) the warning about unsupported options was worked out for
+    unsupported_keys: Option<(GameTitle, LindberghConfig, Vec<String>)>,
 }
 impl Default for LoaderApp {
     fn default() -> Self {
@@ -94,9 +216,44 @@ impl Default for LoaderApp {
             shared_state: SharedState::default(),
             current_game: GameTitle::Unknown,
             current_process: None,
+            loader_info: None,
+            evdev_capture: None,
+            axis_monitor: None,
+            axis_capture: None,
+            gamepad_nav: None,
+            input_test: false,
+            input_monitor: None,
+            jvs_monitor: None,
+            serial_console: None,
+            console_input: String::new(),
+            discovery: None,
+            preflight: None,
+            preflight_check: None,
+            gpu: None,
+            gpu_detection: None,
+            ati_warning: None,
+            unsupported_keys: None,
         }
     }
 }
+// Escape,or Back on a gamepad,leaves the page unless a dialog is open over it
+fn back_pressed(ctx: &egui::Context) -> bool {
+    ctx.memory(|m| m.top_modal_layer().is_none())
+        && ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape))
+}
+// Focuses a dialog's main button when the dialog opens,so Enter and the gamepad act on it
+fn focus_on_open(ui: &egui::Ui, button: &egui::Response) {
+    let pass = ui.ctx().cumulative_pass_nr();
+    let id = button.id.with("shown on pass");
+    let last = ui.data_mut(|d| {
+        let last = d.get_temp::<u64>(id);
+        d.insert_temp(id, pass);
+        last
+    });
+    if last.is_none_or(|x| x + 1 < pass) {
+        button.request_focus();
+    }
+}
 impl LoaderApp {
     fn set_modal(&mut self, data: impl Into<String>, status: ModalStatus) {
         self.modal = Some(ModalInfo {
@@ -118,7 +275,7 @@ impl LoaderApp {
     }
     fn modal_update(&mut self, ctx: &egui::Context) {
         if self.modal.is_some() {
-            Modal::new(egui::Id::new("New Modal")).show(ctx, |ui| {
+            let modal = Modal::new(egui::Id::new("New Modal")).show(ctx, |ui| {
                 ui.horizontal_top(|ui| {
                     ui.vertical_centered(|ui| match self.modal.clone().unwrap().status {
                         ModalStatus::Error => {
@@ -170,13 +327,58 @@ impl LoaderApp {
                         ui.label("And everybody who supported and advised me on ");
                         ui.hyperlink_to("Arcade Community", "https://arcade.community");
                     });
+                    ui.heading("Installed lindbergh-loader");
+                    if let Some(info) = &self.loader_info {
+                        egui::Grid::new("loader info grid")
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                ui.strong("Version:");
+                                ui.label(info.version.as_deref().unwrap_or("Unknown"));
+                                ui.end_row();
+                                ui.strong("Recognised options:");
+                                if info.supported_keys.is_empty() {
+                                    ui.label("Unknown");
+                                } else {
+                                    ui.label(format!(
+                                        "{}/{}",
+                                        info.supported_keys.len(),
+                                        KNOWN_KEYS.len()
+                                    ));
+                                }
+                                ui.end_row();
+                            });
+                    } else {
+                        ui.label("Unable to read ./dynlibs/lindbergh.so");
+                    }
                 }
                 ui.vertical_centered(|ui| {
-                    if ui.button("close").clicked() {
+                    let close = ui.button("close");
+                    focus_on_open(ui, &close);
+                    if close.clicked() {
                         self.modal = None;
                     }
                 });
             });
+            if modal.should_close() {
+                self.modal = None;
+            }
+        }
+    }
+    fn loader_info_update(&mut self) {
+        if self.loader_info.is_none() {
+            match LoaderInfo::detect() {
+                Err(e) => {
+                    self.set_modal(
+                        format!("Unable to inspect ./dynlibs/lindbergh.so:\n{}", e),
+                        ModalStatus::Error,
+                    );
+                    // Assume everything is supported
+                    self.loader_info = Some(LoaderInfo::default());
+                }
+                Result::Ok(info) => {
+                    self.loader_info = Some(info);
+                }
+            }
         }
     }
     fn game_library_update(&mut self) {
@@ -197,7 +399,44 @@ impl LoaderApp {
     }
 }
 impl LoaderApp {
+    // Arrow keys (or a gamepad's d-pad) browse the library,Enter runs the selected game
+    fn library_keyboard_nav(&mut self, ctx: &egui::Context) {
+        if self.modal.is_some()
+            || self.preflight.is_some()
+            || self.preflight_check.is_some()
+            || self.ati_warning.is_some()
+            || self.game_library.is_empty()
+            || ctx.memory(|m| m.focused().is_some())
+        {
+            return;
+        }
+        let (up, down, enter) = ctx.input(|i| {
+            (
+                i.key_pressed(Key::ArrowUp),
+                i.key_pressed(Key::ArrowDown),
+                i.key_pressed(Key::Enter),
+            )
+        });
+        let len = self.game_library.len();
+        let current = self
+            .game_library
+            .iter()
+            .position(|x| GameTitle::from(x) == self.current_game);
+        let next = if up {
+            Some(current.map_or(len - 1, |x| x.saturating_sub(1)))
+        } else if down {
+            Some(current.map_or(0, |x| (x + 1).min(len - 1)))
+        } else {
+            None
+        };
+        if let Some(next) = next {
+            self.current_game = GameTitle::from(&self.game_library[next]);
+        } else if enter && self.current_game != GameTitle::Unknown {
+            self.launch_game(false);
+        }
+    }
     fn main_page_ui(&mut self, ctx: &egui::Context) {
+        self.library_keyboard_nav(ctx);
         egui::TopBottomPanel::top("main page top panel").show(ctx, |ui| {
             ui.menu_button("About", |ui| {
                 ui.close_menu();
@@ -251,6 +490,19 @@ impl LoaderApp {
                             ui.colored_label(Color32::from_rgb(0, 128, 0), "Yes");
                         }
                         ui.end_row();
+                        ui.strong("GPU:");
+                        match &self.gpu {
+                            None => {
+                                ui.weak("Detecting...");
+                            }
+                            Some(gpu) if curr_data.not_working_on_ati && gpu.is_amd() => {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), gpu.describe());
+                            }
+                            Some(gpu) => {
+                                ui.label(gpu.describe());
+                            }
+                        }
+                        ui.end_row();
                     });
                 ui.separator();
                 egui::Grid::new("buttons").num_columns(1).show(ui, |ui| {
@@ -273,12 +525,22 @@ impl LoaderApp {
                                 self.app_state = AppState::ConfigureMapping;
                             }
                             ui.end_row();
+                            if self.current_game.as_gamedata().game_type == Some(GameType::SHOOTING)
+                            {
+                                if ui
+                                    .button(RichText::new("Configure Guns").size(15.0))
+                                    .clicked()
+                                {
+                                    self.app_state = AppState::ConfigureGun;
+                                }
+                                ui.end_row();
+                            }
                             if ui
                                 .button(RichText::new("Run the game").strong().size(15.0))
                                 .clicked()
                                 && self.current_game != GameTitle::Unknown
                             {
-                                self.run_game_with_monitor(false);
+                                self.launch_game(false);
                             }
                             ui.end_row();
                             if ui
@@ -286,7 +548,7 @@ impl LoaderApp {
                                 .clicked()
                                 && self.current_game != GameTitle::Unknown
                             {
-                                self.run_game_with_monitor(true);
+                                self.launch_game(true);
                             }
                             ui.end_row();
                             if ui
@@ -353,7 +615,7 @@ impl LoaderApp {
                                     for i in self.game_library.iter() {
                                         if ui
                                             .selectable_label(
-                                                false,
+                                                GameTitle::from(i) == self.current_game,
                                                 RichText::new(&i.game_title).size(15.5).monospace(),
                                             )
                                             .clicked()
@@ -425,7 +687,7 @@ impl LoaderApp {
                         self.shared_state.new_game_modify = None;
                         self.app_state = AppState::MainPage;
                     }
-                    if ui.button("Cancel").clicked() {
+                    if ui.button("Cancel").clicked() || back_pressed(ui.ctx()) {
                         self.game_library
                             .remove(self.shared_state.new_game_modify.unwrap());
                         self.current_game = GameTitle::Unknown;
@@ -483,25 +745,12 @@ impl LoaderApp {
             } else if let Result::Ok(k) = interfaces {
                 self.shared_state.temp_interface = k;
             }
+            self.refresh_serial_ports();
+            self.refresh_displays();
             self.shared_state.first_run[1] = false;
         }
         if self.shared_state.first_run[2] {
             let cl = self.shared_state.temp_config.clone();
-            if ![
-                (640, 480),
-                (800, 600),
-                (1024, 768),
-                (1280, 1024),
-                (800, 480),
-                (1024, 600),
-                (1280, 768),
-                (1360, 768),
-            ]
-            .contains(&cl.window_size)
-            {
-                self.shared_state.shared_text[0] = cl.window_size.0.to_string();
-                self.shared_state.shared_text[1] = cl.window_size.1.to_string();
-            }
             self.shared_state.shared_text[2] = cl.limit_fps_target.to_string();
             self.shared_state.shared_text[3] = cl.custom_cursor_width.to_string();
             self.shared_state.shared_text[4] = cl.custom_cursor_height.to_string();
@@ -512,6 +761,7 @@ impl LoaderApp {
             egui_alignments::top_horizontal(ui, |ui| {
                 ui.heading(RichText::new("Configure Game").size(35.0).strong());
             });
+            self.unsupported_keys_warning(ui);
             ui.separator();
             egui::ScrollArea::vertical()
                 .id_salt("Configure Game ScrollArea")
@@ -535,44 +785,16 @@ impl LoaderApp {
                                 }
                             }
                             ui.end_row();
-                            ui.label("Window Size");
-                            ui.label("(The size will follow the custom's by default)");
-                            ui.end_row();
-                            ui.label("Preset:");
-                            egui::ComboBox::from_id_salt("window size combobox")
-                                .width(50.0)
-                                .selected_text(format!("{}x{}", cl.window_size.0, cl.window_size.1))
-                                .show_ui(ui, |ui| {
-                                    for i in [
-                                        (640, 480),
-                                        (800, 600),
-                                        (1024, 768),
-                                        (1280, 1024),
-                                        (800, 480),
-                                        (1024, 600),
-                                        (1280, 768),
-                                        (1360, 768),
-                                    ] {
-                                        ui.selectable_value(
-                                            &mut self.shared_state.temp_config.window_size,
-                                            i,
-                                            format!("{}x{}", i.0, i.1),
-                                        );
-                                    }
-                                });
-                            ui.end_row();
-                            ui.label("Custom width:");
-                            ui.text_edit_singleline(&mut self.shared_state.shared_text[0]);
-                            ui.end_row();
-                            ui.label("Custom Height:");
-                            ui.text_edit_singleline(&mut self.shared_state.shared_text[1]);
-                            ui.end_row();
                             ui.label("Fullscreen:");
                             ui.checkbox(&mut self.shared_state.temp_config.fullscreen, "");
                             ui.end_row();
-                            ui.label("Disable SDL:");
-                            ui.checkbox(&mut self.shared_state.temp_config.disable_sdl, "");
-                            ui.end_row();
+                            self.display_picker(ui);
+                            self.resolution_editor(ui);
+                            if self.option_shown("NO_SDL") {
+                                ui.label("Disable SDL:");
+                                ui.checkbox(&mut self.shared_state.temp_config.disable_sdl, "");
+                                ui.end_row();
+                            }
                             ui.label("Reigon:");
                             egui::ComboBox::from_id_salt("reigon combobox")
                                 .selected_text(cl.game_region.to_string())
@@ -594,22 +816,32 @@ impl LoaderApp {
                                     );
                                 });
                             ui.end_row();
-                            ui.label("Freeplay:");
-                            ui.checkbox(&mut self.shared_state.temp_config.freeplay, "");
-                            ui.end_row();
+                            if self.option_shown("FREEPLAY") {
+                                ui.label("Freeplay:");
+                                ui.checkbox(&mut self.shared_state.temp_config.freeplay, "");
+                                ui.end_row();
+                            }
                             ui.label("Emulate JVS:");
                             ui.checkbox(&mut self.shared_state.temp_config.emulate_jvs, "");
                             ui.end_row();
                             if !self.get_config().emulate_jvs {
-                                ui.label("Enter serial port:");
-                                ui.text_edit_singleline(
-                                    &mut self.shared_state.temp_config.jvs_path,
-                                );
+                                ui.label("JVS serial port:");
+                                self.serial_port_picker(ui, "jvs port", |x| &mut x.jvs_path);
+                                ui.end_row();
+                                ui.label("");
+                                if ui.button("Diagnose Board").clicked() {
+                                    self.jvs_monitor = Some(JvsMonitor::start(Path::new(
+                                        &self.shared_state.temp_config.jvs_path,
+                                    )));
+                                    self.app_state = AppState::JvsDiagnostics;
+                                }
                                 ui.end_row();
                             }
-                            if GameTitle::from(self.get_game()) == GameTitle::Lets_Go_Jungle_Special
-                                || GameTitle::from(self.get_game())
-                                    == GameTitle::The_House_Of_The_Dead_4_Special
+                            if self.option_shown("EMULATE_RIDEBOARD")
+                                && (GameTitle::from(self.get_game())
+                                    == GameTitle::Lets_Go_Jungle_Special
+                                    || GameTitle::from(self.get_game())
+                                        == GameTitle::The_House_Of_The_Dead_4_Special)
                             {
                                 ui.label("Emulate Rideboard:");
                                 ui.checkbox(
@@ -618,14 +850,27 @@ impl LoaderApp {
                                 );
                                 if !self.get_config().emulate_rideboard {
                                     ui.end_row();
-                                    ui.label("Enter serial port:");
-                                    ui.text_edit_singleline(
-                                        &mut self.shared_state.temp_config.serial_port1,
-                                    );
+                                    ui.label("Serial port:");
+                                    self.serial_port_picker(ui, "rideboard port", |x| {
+                                        &mut x.serial_port1
+                                    });
+                                    ui.end_row();
+                                    ui.label("");
+                                    if ui.button("Test Board").clicked() {
+                                        self.open_serial_console(
+                                            BoardKind::Ride,
+                                            cl.serial_port1.clone(),
+                                        );
+                                    }
                                 }
                                 ui.end_row();
                             }
-                            if self.get_game().game_type == Some(GameType::DRIVING) {
+                            if self.option_shown("EMULATE_DRIVEBOARD")
+                                && matches!(
+                                    self.get_game().game_type,
+                                    Some(GameType::DRIVING | GameType::HARLEY)
+                                )
+                            {
                                 ui.label("Emulate driveboard:");
                                 ui.checkbox(
                                     &mut self.shared_state.temp_config.emulate_driveboard,
@@ -633,14 +878,24 @@ impl LoaderApp {
                                 );
                                 if !self.get_config().emulate_driveboard {
                                     ui.end_row();
-                                    ui.label("Enter serial port:");
-                                    ui.text_edit_singleline(
-                                        &mut self.shared_state.temp_config.serial_port1,
-                                    );
+                                    ui.label("Serial port:");
+                                    self.serial_port_picker(ui, "driveboard port", |x| {
+                                        &mut x.serial_port1
+                                    });
+                                    ui.end_row();
+                                    ui.label("");
+                                    if ui.button("Test Board").clicked() {
+                                        self.open_serial_console(
+                                            BoardKind::Drive,
+                                            cl.serial_port1.clone(),
+                                        );
+                                    }
                                 }
                                 ui.end_row();
                             }
-                            if GameTitle::from(self.get_game()) == GameTitle::Outrun_2_SP_SDX {
+                            if GameTitle::from(self.get_game()) == GameTitle::Outrun_2_SP_SDX
+                                && self.option_shown("EMULATE_MOTIONBOARD")
+                            {
                                 ui.label("Emulate motionboard:");
                                 ui.checkbox(
                                     &mut self.shared_state.temp_config.emulate_motionboard,
@@ -648,10 +903,18 @@ impl LoaderApp {
                                 );
                                 if !self.get_config().emulate_motionboard {
                                     ui.end_row();
-                                    ui.label("Enter serial port:");
-                                    ui.text_edit_singleline(
-                                        &mut self.shared_state.temp_config.serial_port2,
-                                    );
+                                    ui.label("Serial port:");
+                                    self.serial_port_picker(ui, "motionboard port", |x| {
+                                        &mut x.serial_port2
+                                    });
+                                    ui.end_row();
+                                    ui.label("");
+                                    if ui.button("Test Board").clicked() {
+                                        self.open_serial_console(
+                                            BoardKind::Motion,
+                                            cl.serial_port2.clone(),
+                                        );
+                                    }
                                 }
                                 ui.end_row();
                             }
@@ -661,32 +924,39 @@ impl LoaderApp {
                             ui.label("EEPROM path:");
                             ui.text_edit_singleline(&mut self.shared_state.temp_config.eeprom_path);
                             ui.end_row();
-                            ui.label("GPU Vendor:");
-                            egui::ComboBox::from_id_salt("gpuv cbb")
-                                .selected_text(cl.gpu_vendor.to_string())
-                                .show_ui(ui, |ui| {
-                                    for i in [
-                                        GpuType::AMD,
-                                        GpuType::ATI,
-                                        GpuType::Intel,
-                                        GpuType::Nvidia,
-                                        GpuType::AutoDetect,
-                                        GpuType::Unknown,
-                                    ] {
-                                        ui.selectable_value(
-                                            &mut self.shared_state.temp_config.gpu_vendor,
-                                            i.clone(),
-                                            (i).to_string(),
-                                        );
-                                    }
-                                });
-                            ui.end_row();
-                            ui.label("Show debug message:");
-                            ui.checkbox(&mut self.shared_state.temp_config.debug_message, "");
-                            ui.end_row();
-                            if GameTitle::from(self.get_game()) == GameTitle::Hummer
-                                || GameTitle::from(self.get_game()) == GameTitle::Hummer_Extreme
-                                || GameTitle::from(self.get_game()) == GameTitle::Hummer_Extreme_MDX
+                            if self.option_shown("GPU_VENDOR") {
+                                ui.label("GPU Vendor:");
+                                egui::ComboBox::from_id_salt("gpuv cbb")
+                                    .selected_text(cl.gpu_vendor.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for i in [
+                                            GpuType::AMD,
+                                            GpuType::ATI,
+                                            GpuType::Intel,
+                                            GpuType::Nvidia,
+                                            GpuType::AutoDetect,
+                                            GpuType::Unknown,
+                                        ] {
+                                            ui.selectable_value(
+                                                &mut self.shared_state.temp_config.gpu_vendor,
+                                                i.clone(),
+                                                (i).to_string(),
+                                            );
+                                        }
+                                    });
+                                ui.end_row();
+                                self.gpu_recommendation(ui);
+                            }
+                            if self.option_shown("DEBUG_MSGS") {
+                                ui.label("Show debug message:");
+                                ui.checkbox(&mut self.shared_state.temp_config.debug_message, "");
+                                ui.end_row();
+                            }
+                            if self.option_shown("HUMMER_FLICKER_FIX")
+                                && (GameTitle::from(self.get_game()) == GameTitle::Hummer
+                                    || GameTitle::from(self.get_game()) == GameTitle::Hummer_Extreme
+                                    || GameTitle::from(self.get_game())
+                                        == GameTitle::Hummer_Extreme_MDX)
                             {
                                 ui.label("Hummer Flicker Fix:");
                                 ui.checkbox(
@@ -695,10 +965,17 @@ impl LoaderApp {
                                 );
                                 ui.end_row();
                             }
-                            ui.label("Keep aspect ratio:");
-                            ui.checkbox(&mut self.shared_state.temp_config.keep_aspect_ratio, "");
-                            ui.end_row();
-                            if GameTitle::from(self.get_game()) == GameTitle::Outrun_2_SP_SDX {
+                            if self.option_shown("KEEP_ASPECT_RATIO") {
+                                ui.label("Keep aspect ratio:");
+                                ui.checkbox(
+                                    &mut self.shared_state.temp_config.keep_aspect_ratio,
+                                    "",
+                                );
+                                ui.end_row();
+                            }
+                            if GameTitle::from(self.get_game()) == GameTitle::Outrun_2_SP_SDX
+                                && self.option_shown("OUTRUN_LENS_GLARE_ENABLED")
+                            {
                                 ui.label("Glare effect:");
                                 ui.checkbox(
                                     &mut self.shared_state.temp_config.outrun_lens_glare_enable,
@@ -706,10 +983,14 @@ impl LoaderApp {
                                 );
                                 ui.end_row();
                             }
-                            ui.label("Enable border:");
-                            ui.checkbox(&mut self.shared_state.temp_config.border_enabled, "");
-                            ui.end_row();
-                            if self.shared_state.temp_config.border_enabled {
+                            if self.option_shown("BORDER_ENABLED") {
+                                ui.label("Enable border:");
+                                ui.checkbox(&mut self.shared_state.temp_config.border_enabled, "");
+                                ui.end_row();
+                            }
+                            if self.option_shown("BORDER_ENABLED")
+                                && self.shared_state.temp_config.border_enabled
+                            {
                                 ui.label("White border:");
                                 ui.add(
                                     egui::Slider::new(
@@ -729,15 +1010,24 @@ impl LoaderApp {
                                 );
                                 ui.end_row();
                             }
-                            ui.label("Enable FPS limiter:");
-                            ui.checkbox(&mut self.shared_state.temp_config.enable_fps_limiter, "");
-                            ui.end_row();
-                            if self.shared_state.temp_config.enable_fps_limiter {
+                            if self.option_shown("FPS_LIMITER_ENABLED") {
+                                ui.label("Enable FPS limiter:");
+                                ui.checkbox(
+                                    &mut self.shared_state.temp_config.enable_fps_limiter,
+                                    "",
+                                );
+                                ui.end_row();
+                            }
+                            if self.option_shown("FPS_LIMITER_ENABLED")
+                                && self.shared_state.temp_config.enable_fps_limiter
+                            {
                                 ui.label("FPS limit:");
                                 ui.text_edit_singleline(&mut self.shared_state.shared_text[2]);
                                 ui.end_row();
                             }
-                            if self.current_game == GameTitle::Outrun_2_SP_SDX {
+                            if self.current_game == GameTitle::Outrun_2_SP_SDX
+                                && self.option_shown("SKIP_OUTRUN_CABINET_CHECK")
+                            {
                                 ui.label("Skip cabinet check:");
                                 ui.checkbox(
                                     &mut self.shared_state.temp_config.skip_outrun_cabinet_check,
@@ -745,9 +1035,10 @@ impl LoaderApp {
                                 );
                                 ui.end_row();
                             }
-                            if self.current_game == GameTitle::Taisen_Mahjong_4
-                                || self.current_game
-                                    == GameTitle::Taisen_Mahjong_4_Evolution
+                            if self.option_shown("MJ4_ENABLED_ALL_THE_TIME")
+                                && (self.current_game == GameTitle::Taisen_Mahjong_4
+                                    || self.current_game
+                                        == GameTitle::Taisen_Mahjong_4_Evolution)
                             {
                                 ui.label("Mahjong 4 enable all the time:");
                                 ui.checkbox(
@@ -756,31 +1047,43 @@ impl LoaderApp {
                                 );
                                 ui.end_row();
                             }
-                            ui.label("Lindbergh color:");
-                            egui::ComboBox::from_id_salt("color combobox")
-                                .selected_text(cl.lindbergh_color.to_string())
-                                .show_ui(ui, |ui| {
-                                    for i in [
-                                        LindberghColor::BLUE,
-                                        LindberghColor::RED,
-                                        LindberghColor::REDEX,
-                                        LindberghColor::SILVER,
-                                        LindberghColor::YELLOW,
-                                    ] {
-                                        ui.selectable_value(
-                                            &mut self.shared_state.temp_config.lindbergh_color,
-                                            i.clone(),
-                                            i.to_string(),
-                                        );
-                                    }
-                                });
-                            ui.end_row();
-                            ui.label("Disable Builtin Font:");
-                            ui.checkbox(&mut self.shared_state.temp_config.disable_builtin_font, "");
-                            ui.end_row();
-                            ui.label("Disable Builtin Logos:");
-                            ui.checkbox(&mut self.shared_state.temp_config.disable_builtin_logos, "");
-                            ui.end_row();
+                            if self.option_shown("LINDBERGH_COLOUR") {
+                                ui.label("Lindbergh color:");
+                                egui::ComboBox::from_id_salt("color combobox")
+                                    .selected_text(cl.lindbergh_color.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for i in [
+                                            LindberghColor::BLUE,
+                                            LindberghColor::RED,
+                                            LindberghColor::REDEX,
+                                            LindberghColor::SILVER,
+                                            LindberghColor::YELLOW,
+                                        ] {
+                                            ui.selectable_value(
+                                                &mut self.shared_state.temp_config.lindbergh_color,
+                                                i.clone(),
+                                                i.to_string(),
+                                            );
+                                        }
+                                    });
+                                ui.end_row();
+                            }
+                            if self.option_shown("DISABLE_BUILTIN_FONT") {
+                                ui.label("Disable Builtin Font:");
+                                ui.checkbox(
+                                    &mut self.shared_state.temp_config.disable_builtin_font,
+                                    "",
+                                );
+                                ui.end_row();
+                            }
+                            if self.option_shown("DISABLE_BUILTIN_LOGOS") {
+                                ui.label("Disable Builtin Logos:");
+                                ui.checkbox(
+                                    &mut self.shared_state.temp_config.disable_builtin_logos,
+                                    "",
+                                );
+                                ui.end_row();
+                            }
                             ui.label("Hide Cursor:");
                             ui.checkbox(&mut self.shared_state.temp_config.hide_cursor, "");
                             ui.end_row();
@@ -814,8 +1117,10 @@ impl LoaderApp {
                             if self.current_game == GameTitle::InitialD_4 || self.current_game == GameTitle::InitalD_4_Export || self.current_game == GameTitle::InitialD_5_Japan || self.current_game == GameTitle::InitalD_5_Export_Ver_2 || self.current_game == GameTitle::InitalD_5_Export_Ver_4 {
                                 ui.label("Inital D 4/5 Seat1 IP:");
                                 ui.text_edit_singleline(&mut self.shared_state.temp_config.id45_ip_seat[0]);
+                                ui.end_row();
                                 ui.label("Inital D 4/5 Seat2 IP:");
                                 ui.text_edit_singleline(&mut self.shared_state.temp_config.id45_ip_seat[1]);
+                                ui.end_row();
                             }
                             if self.current_game == GameTitle::Outrun_2_SP_SDX {
                                 ui.label("Network Card Name:");
@@ -859,6 +1164,13 @@ impl LoaderApp {
                                     ui.end_row();
                                 }
                             }
+                            if LinkKind::from_title(&self.current_game).is_some() {
+                                ui.label("");
+                                if ui.button("Link Setup Wizard").clicked() {
+                                    self.open_link_wizard();
+                                }
+                                ui.end_row();
+                            }
                             if self.current_game == GameTitle::The_House_Of_The_Dead_4 {
                                 ui.strong("CPU Frequency is measured by system.");
                                 ui.end_row();
@@ -866,7 +1178,7 @@ impl LoaderApp {
                                 ui.label(format!("{} Ghz",&self.shared_state.temp_config.cpu_freq.to_string()));
                                 ui.end_row();
                             }
-                            if self.current_game == GameTitle::Virtua_Tennis_3 {
+                            if self.current_game.uses_card_reader() {
                                 ui.label("Emulate Card Reader:");
                                 ui.checkbox(&mut self.shared_state.temp_config.emulate_cardreader, "");
                                 ui.end_row();
@@ -875,6 +1187,17 @@ impl LoaderApp {
                                 ui.end_row();
                                 ui.label("Card Reader 2:");
                                 ui.text_edit_singleline(&mut self.shared_state.temp_config.card_file[1]);
+                                ui.end_row();
+                                if cl.card_file[0] == cl.card_file[1] {
+                                    ui.label("");
+                                    ui.colored_label(Color32::RED, "Both players use the same card file");
+                                    ui.end_row();
+                                }
+                                ui.label("");
+                                if ui.button("Manage Cards").clicked() {
+                                    self.open_card_manager();
+                                }
+                                ui.end_row();
                             }
                             ui.label("Or");
                             if ui.button("Import from existing lindbergh config file").clicked() {
@@ -909,41 +1232,30 @@ impl LoaderApp {
             egui_alignments::center_horizontal(ui, |ui| {
                 if ui.button("Save").clicked() {
                     let v = self.shared_state.clone();
-                    if let Err(e) = v.assign_conf(&mut self.shared_state.temp_config) {
-                        self.set_modal(
-                            format!("Error occurred while parsing data \"{}\"", e),
-                            ModalStatus::Error,
-                        );
-                    } else if let Err(e) = self
-                        .shared_state
-                        .temp_config
-                        .write_to_lindbergh_conf(&self.current_game)
+                    if let Err(e) =
+                        v.assign_conf(&mut self.shared_state.temp_config, &self.current_game)
                     {
                         self.set_modal(
-                            format!("Error occurred while writing data \"{}\"", e),
+                            format!("Error occurred while parsing data \"{}\"", e),
                             ModalStatus::Error,
                         );
                     } else {
-                        *self.get_config_mut() = self.shared_state.temp_config.clone();
-                        self.set_modal(
-                            format!(
-                                "Configuration successfully saved into ./config/{:?}.conf",
-                                self.current_game
-                            ),
-                            ModalStatus::Info,
-                        );
-                        self.app_state = AppState::MainPage;
-                        self.shared_state = SharedState::default();
+                        self.preview_changes();
                     }
                 }
-                if ui.button("Cancel").clicked() {
-                    self.app_state = AppState::MainPage;
-                    self.shared_state = SharedState::default();
+                if ui.button("Cancel").clicked() || back_pressed(ui.ctx()) {
+                    self.try_leave_configure_page();
                 }
+                self.history_buttons(ui);
             });
         });
+        self.config_changes_modal(ctx);
+        self.revisions_modal(ctx);
+        self.history_update(ctx);
     }
-    fn configure_mapping_ui(&mut self, ctx: &egui::Context) {
+    // Loads the config and evdev devices for the mapping and gun pages
+    // false if we don't know which game is being configured
+    fn prepare_input_page(&mut self) -> bool {
         for (cnt, i) in self.game_library.iter().enumerate() {
             if GameTitle::from(i) == self.current_game {
                 self.shared_state.new_game_modify = Some(cnt);
@@ -955,10 +1267,16 @@ impl LoaderApp {
                 ModalStatus::Error,
             );
             self.app_state = AppState::MainPage;
-            return;
+            return false;
         }
         let mut config = LindberghConfig::default();
         let p = format!("./config/{:?}.conf", self.current_game);
+        // New configs start with the keys suggested for this game
+        if !fs::exists(&p).unwrap()
+            && let Some(sdl) = config.input_method.get_sdl_mut()
+        {
+            *sdl = InputTemplate::from(&self.current_game).default_sdl();
+        }
         if fs::exists(&p).unwrap() && self.shared_state.first_run[0] {
             if let Err(e) = config.read_from_lindbergh_conf_by_title(&self.current_game) {
                 self.set_modal(format!("config {} exists,but error occurred while reading data:\n{}\nCurrent game: {:?}",&p,e,self.current_game), ModalStatus::Error);
@@ -969,11 +1287,39 @@ impl LoaderApp {
         if self.shared_state.temp_config.input_method == Keymap::default() {
             self.shared_state.temp_config = config;
         }
+        if self.shared_state.first_run[3] {
+            match evdev::list_devices() {
+                Err(e) => {
+                    self.set_modal(
+                        format!("Unable to list evdev devices:\n{}", e),
+                        ModalStatus::Error,
+                    );
+                }
+                Result::Ok(devices) => {
+                    self.shared_state.evdev_devices = devices;
+                }
+            }
+            self.refresh_presets();
+            self.shared_state.first_run[3] = false;
+        }
+        true
+    }
+    fn configure_mapping_ui(&mut self, ctx: &egui::Context) {
+        if !self.prepare_input_page() {
+            return;
+        }
+        self.evdev_capture_update(ctx);
+        self.axis_monitor_update(ctx);
+        self.input_tester_ui(ctx);
+        let conflicts = self.shared_state.temp_config.input_method.conflicts();
         let cl = self.shared_state.temp_config.input_method.clone();
         egui::CentralPanel::default().show(ctx, |ui| {
             egui_alignments::top_horizontal(ui, |ui| {
                 ui.heading(RichText::new("Configure KeyMap").size(35.0).strong());
             });
+            self.unsupported_keys_warning(ui);
+            ui.separator();
+            self.presets_ui(ui);
             ui.separator();
             egui::ScrollArea::vertical()
                 .auto_shrink(false)
@@ -1003,296 +1349,2248 @@ impl LoaderApp {
                                 });
                             ui.end_row();
                             let cl = self.shared_state.temp_config.input_method.clone();
+                            let template = InputTemplate::from(&self.current_game);
                             if cl.has_sdl() {
-                                let sdl_keymap = cl.get_sdlkeymap().unwrap();
-                                let mut_sdl_keymap = self
-                                    .shared_state
-                                    .temp_config
-                                    .input_method
-                                    .get_sdlkeymap_mut()
-                                    .unwrap();
                                 ui.strong("SDL/X11 Keymap:");
+                                if ui
+                                    .button("Reset to game defaults")
+                                    .on_hover_text("Bind the keys suggested for this game")
+                                    .clicked()
+                                {
+                                    *self
+                                        .shared_state
+                                        .temp_config
+                                        .input_method
+                                        .get_sdl_mut()
+                                        .unwrap() = template.default_sdl();
+                                }
                                 ui.end_row();
                                 ui.label("To modify a key,hover on the button and press the key on the keyboard.");
                                 ui.end_row();
-                                ui.label("Test Key:");
-                                if ui.button(sdl_keymap.test.unwrap().name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.test = Some(*k);
+                                for player in 1..=2 {
+                                    for field in template.fields(player) {
+                                        let slot = BindingSlot {
+                                            evdev: false,
+                                            player,
+                                            field,
+                                        };
+                                        binding_label(
+                                            ui,
+                                            &template,
+                                            slot,
+                                            &slot.conflicts_in(&conflicts),
+                                        );
+                                        let sdl = self
+                                            .shared_state
+                                            .temp_config
+                                            .input_method
+                                            .get_sdl_mut()
+                                            .unwrap();
+                                        let keymap = if player == 1 {
+                                            &mut sdl.player1
+                                        } else {
+                                            &mut sdl.player2
+                                        };
+                                        let name = keymap
+                                            .get(field)
+                                            .map(|k| k.name())
+                                            .unwrap_or("None".into());
+                                        if ui.button(name).hovered() {
+                                            // egui reports modifiers separately from keys
+                                            let modifiers = ctx.input(|i| i.modifiers);
+                                            for (down, keycode) in [
+                                                (modifiers.shift, KEYCODE_SHIFT),
+                                                (modifiers.ctrl, KEYCODE_CONTROL),
+                                                (modifiers.alt, KEYCODE_ALT),
+                                            ] {
+                                                if down {
+                                                    keymap.set(field, SdlKey::Raw(keycode));
+                                                }
+                                            }
+                                            for k in Key::ALL {
+                                                if ctx.input(|i| i.key_down(*k)) {
+                                                    keymap.set(field, (*k).into());
+                                                    break;
+                                                }
+                                            }
                                         }
+                                        ui.end_row();
                                     }
                                 }
+                            }
+                            ui.end_row();
+                            if cl.has_evdev() {
+                                ui.strong("Evdev Keymap:");
                                 ui.end_row();
-                                ui.label("Player 1 Start Key:");
-                                if ui.button(sdl_keymap.start.name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.start = *k;
+                                ui.label("Capture Device:");
+                                let selected = self
+                                    .shared_state
+                                    .evdev_device
+                                    .and_then(|x| self.shared_state.evdev_devices.get(x))
+                                    .map(|x| x.name.clone())
+                                    .unwrap_or("None".into());
+                                egui::ComboBox::from_id_salt("evdev device combobox")
+                                    .selected_text(selected)
+                                    .show_ui(ui, |ui| {
+                                        for (cnt, i) in
+                                            self.shared_state.evdev_devices.iter().enumerate()
+                                        {
+                                            ui.selectable_value(
+                                                &mut self.shared_state.evdev_device,
+                                                Some(cnt),
+                                                format!(
+                                                "{}{} ({})",
+                                                if gamepad::is_gamepad(i) { "🎮 " } else { "" },
+                                                i.name,
+                                                i.path.display()
+                                            ),
+                                            );
                                         }
-                                    }
-                                }
+                                    });
                                 ui.end_row();
-                                ui.label("Player 1 Service Key:");
-                                if ui.button(sdl_keymap.service.name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.service = *k;
-                                            break;
-                                        }
-                                    }
-                                }
+                                ui.label("To modify a key,click 🎯 and press the key on the capture device,or type its name.");
                                 ui.end_row();
-                                ui.label("Player 1 Coin Key:");
-                                if ui.button(sdl_keymap.coin.unwrap().name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.coin = Some(*k);
-                                            break;
+                                for player in 1..=2 {
+                                    for field in template.fields(player) {
+                                        // Evdev has no coin key
+                                        if field == KeymapField::Coin {
+                                            continue;
                                         }
-                                    }
-                                }
-                                ui.end_row();
-                                ui.label("Player 1 Up Key:");
-                                if ui.button(sdl_keymap.up.name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.up = *k;
-                                            break;
+                                        let slot = BindingSlot {
+                                            evdev: true,
+                                            player,
+                                            field,
+                                        };
+                                        binding_label(
+                                            ui,
+                                            &template,
+                                            slot,
+                                            &slot.conflicts_in(&conflicts),
+                                        );
+                                        let evdev = self
+                                            .shared_state
+                                            .temp_config
+                                            .input_method
+                                            .get_evdev_mut()
+                                            .unwrap();
+                                        let keymap = if player == 1 {
+                                            &mut evdev.player1
+                                        } else {
+                                            &mut evdev.player2
+                                        };
+                                        // Unbound inputs stay None until something is typed
+                                        let mut text = keymap.get(field).cloned().unwrap_or_default();
+                                        if ui.text_edit_singleline(&mut text).changed() {
+                                            if text.is_empty() {
+                                                keymap.unset(field);
+                                            } else {
+                                                keymap.set(field, text);
+                                            }
                                         }
+                                        self.capture_button(
+                                            ui,
+                                            CaptureTarget::Key(player, field),
+                                        );
+                                        ui.end_row();
                                     }
                                 }
-                                ui.end_row();
-                                ui.label("Player 1 Down Key:");
-                                if ui.button(sdl_keymap.down.name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.down = *k;
-                                            break;
-                                        }
+                                ui.strong("Analogue Axes:");
+                                let monitoring = self.axis_monitor.is_some();
+                                if ui
+                                    .button(if monitoring {
+                                        "Stop live readout"
+                                    } else {
+                                        "Start live readout"
+                                    })
+                                    .clicked()
+                                {
+                                    if monitoring {
+                                        self.axis_monitor = None;
+                                        self.axis_capture = None;
+                                    } else {
+                                        self.start_axis_monitor();
                                     }
                                 }
                                 ui.end_row();
-                                ui.label("Player 1 Left Key:");
-                                if ui.button(sdl_keymap.left.name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.left = *k;
-                                            break;
-                                        }
-                                    }
+                                ui.label("To assign an axis,click 🎯 and move it all the way on the capture device.");
+                                ui.end_row();
+                                for channel in 0..4 {
+                                    ui.label(format!("{}:", template.analogue_label(channel)));
+                                    let evdev = self
+                                        .shared_state
+                                        .temp_config
+                                        .input_method
+                                        .get_evdev_mut()
+                                        .unwrap();
+                                    ui.text_edit_singleline(&mut evdev.analogues[channel]);
+                                    let state = self
+                                        .axis_monitor
+                                        .as_ref()
+                                        .and_then(|x| x.1.get(&evdev.analogues[channel]));
+                                    self.axis_capture_button(ui, channel);
+                                    ui.end_row();
+                                    ui.label("Deadzone:");
+                                    let deadzone = &mut self
+                                        .shared_state
+                                        .temp_config
+                                        .input_method
+                                        .get_evdev_mut()
+                                        .unwrap()
+                                        .analogue_deadzones[channel];
+                                    // Deadzones are in raw axis units,so follow the range seen on the device
+                                    let range = state
+                                        .map(|x| (x.max - x.min).max(0) as u32)
+                                        .unwrap_or_default()
+                                        .max(255)
+                                        .max(deadzone.0)
+                                        .max(deadzone.1)
+                                        .max(deadzone.2);
+                                    ui.vertical(|ui| {
+                                        ui.add(egui::Slider::new(&mut deadzone.0, 0..=range).text("Min"));
+                                        ui.add(egui::Slider::new(&mut deadzone.1, 0..=range).text("Center"));
+                                        ui.add(egui::Slider::new(&mut deadzone.2, 0..=range).text("Max"));
+                                    });
+                                    ui.vertical(|ui| {
+                                        axis_bar(ui, state, *deadzone);
+                                        ui.label(match state {
+                                            Some(x) => format!("{} ({} to {})", x.value, x.min, x.max),
+                                            None => "No readout".into(),
+                                        });
+                                    });
+                                    ui.end_row();
                                 }
-                                ui.end_row();
-                                ui.label("Player 1 Right Key:");
-                                if ui.button(sdl_keymap.right.name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.right = *k;
-                                            break;
-                                        }
-                                    }
+                            }
+                        });
+                    });
+                });
+        });
+        egui::TopBottomPanel::bottom("config mapping btm panel").show(ctx, |ui| {
+            egui_alignments::center_horizontal(ui, |ui| {
+                if ui.button("Save").clicked() {
+                    self.preview_changes();
+                }
+                if ui.button("Cancel").clicked() || back_pressed(ui.ctx()) {
+                    self.try_leave_configure_page();
+                }
+                self.history_buttons(ui);
+                if ui
+                    .selectable_label(self.input_test, "Test Inputs")
+                    .clicked()
+                {
+                    if self.input_test {
+                        self.input_test = false;
+                        self.input_monitor = None;
+                    } else {
+                        self.start_input_test();
+                    }
+                }
+            });
+        });
+        self.config_changes_modal(ctx);
+        self.revisions_modal(ctx);
+        self.history_update(ctx);
+    }
+    fn configure_gun_ui(&mut self, ctx: &egui::Context) {
+        if !self.prepare_input_page() {
+            return;
+        }
+        self.evdev_capture_update(ctx);
+        self.axis_monitor_update(ctx);
+        let cl = self.shared_state.temp_config.input_method.clone();
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui_alignments::top_horizontal(ui, |ui| {
+                ui.heading(RichText::new("Configure Guns").size(35.0).strong());
+            });
+            self.unsupported_keys_warning(ui);
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .auto_shrink(false)
+                .show(ui, |ui| {
+                    egui_alignments::top_horizontal(ui, |ui| {
+                        egui::Grid::new("gun grid").show(ui, |ui| {
+                            ui.label("Hide Cursor");
+                            ui.checkbox(&mut self.shared_state.temp_config.hide_cursor, "");
+                            ui.end_row();
+                            ui.strong("Lightguns:");
+                            ui.end_row();
+                            if !cl.has_evdev() {
+                                ui.label("Lightguns are read through evdev.");
+                                if ui.button("Enable evdev input").clicked() {
+                                    self.shared_state.temp_config.input_method = cl.into_both();
                                 }
                                 ui.end_row();
-                                ui.label("Player 1 Button 1 Key:");
-                                if ui.button(sdl_keymap.button1.name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.button1 = *k;
-                                            break;
-                                        }
+                                return;
+                            }
+                            ui.label("Lightgun Device:");
+                            let selected = self
+                                .shared_state
+                                .evdev_device
+                                .and_then(|x| self.shared_state.evdev_devices.get(x))
+                                .map(|x| x.name.clone())
+                                .unwrap_or("None".into());
+                            egui::ComboBox::from_id_salt("gun device combobox")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    // Known lightguns first
+                                    let mut devices = self
+                                        .shared_state
+                                        .evdev_devices
+                                        .iter()
+                                        .enumerate()
+                                        .collect::<Vec<(usize, &InputDevice)>>();
+                                    devices.sort_by_key(|x| !x.1.is_lightgun());
+                                    for (cnt, i) in devices {
+                                        ui.selectable_value(
+                                            &mut self.shared_state.evdev_device,
+                                            Some(cnt),
+                                            format!(
+                                                "{}{} ({})",
+                                                if i.is_lightgun() { "🔫 " } else { "" },
+                                                i.name,
+                                                i.path.display()
+                                            ),
+                                        );
                                     }
+                                });
+                            ui.end_row();
+                            ui.label("To modify an input,click 🎯 and aim or press it on the lightgun,or type its name.");
+                            ui.end_row();
+                            for player in 1..=2 {
+                                ui.strong(format!("Player {}", player));
+                                if ui
+                                    .button("Fill from device")
+                                    .on_hover_text("Use the axes and buttons most lightguns report in mouse mode")
+                                    .clicked()
+                                {
+                                    self.fill_gun_from_device(player);
                                 }
                                 ui.end_row();
-                                ui.label("Player 1 Button 2 Key:");
-                                if ui.button(sdl_keymap.button2.name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.button2 = *k;
-                                            break;
-                                        }
-                                    }
+                                for (axis, label) in ["Aim X", "Aim Y"].iter().enumerate() {
+                                    let channel = (player - 1) * 2 + axis;
+                                    ui.label(*label);
+                                    let evdev = self
+                                        .shared_state
+                                        .temp_config
+                                        .input_method
+                                        .get_evdev_mut()
+                                        .unwrap();
+                                    ui.text_edit_singleline(&mut evdev.analogues[channel]);
+                                    self.axis_capture_button(ui, channel);
+                                    ui.end_row();
                                 }
-                                ui.end_row();
-                                ui.label("Player 1 Button 3 Key:");
-                                if ui.button(sdl_keymap.button3.name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.button3 = *k;
-                                            break;
-                                        }
-                                    }
+                                for (field, label) in [
+                                    (KeymapField::Button(1), "Trigger"),
+                                    (KeymapField::Button(2), "Reload"),
+                                ] {
+                                    ui.label(label);
+                                    let evdev = self
+                                        .shared_state
+                                        .temp_config
+                                        .input_method
+                                        .get_evdev_mut()
+                                        .unwrap();
+                                    let keymap = if player == 1 {
+                                        &mut evdev.player1
+                                    } else {
+                                        &mut evdev.player2
+                                    };
+                                    ui.text_edit_singleline(keymap.get_mut(field).unwrap());
+                                    self.capture_button(ui, CaptureTarget::Key(player, field));
+                                    ui.end_row();
                                 }
-                                ui.end_row();
-                                ui.label("Player 1 Button 4 Key:");
-                                if ui.button(sdl_keymap.button4.name()).hovered() {
-                                    for k in Key::ALL {
-                                        if ctx.input(|i| i.key_down(*k)) {
-                                            mut_sdl_keymap.button4 = *k;
-                                            break;
+                            }
+                        });
+                    });
+                });
+        });
+        egui::TopBottomPanel::bottom("config gun btm panel").show(ctx, |ui| {
+            egui_alignments::center_horizontal(ui, |ui| {
+                if ui.button("Save").clicked() {
+                    self.preview_changes();
+                }
+                if ui.button("Cancel").clicked() || back_pressed(ui.ctx()) {
+                    self.try_leave_configure_page();
+                }
+                self.history_buttons(ui);
+            });
+        });
+        self.config_changes_modal(ctx);
+        self.revisions_modal(ctx);
+        self.history_update(ctx);
+    }
+    fn jvs_diagnostics_ui(&mut self, ctx: &egui::Context) {
+        let Some(monitor) = &mut self.jvs_monitor else {
+            self.app_state = AppState::ConfigureGame;
+            return;
+        };
+        monitor.update();
+        // The board is polled on another thread
+        ctx.request_repaint_after(Duration::from_millis(50));
+        let mut leave = false;
+        egui::TopBottomPanel::bottom("jvs diagnostics btm panel").show(ctx, |ui| {
+            egui_alignments::center_horizontal(ui, |ui| {
+                if ui.button("Retry").clicked() {
+                    *monitor = JvsMonitor::start(&monitor.path.clone());
+                }
+                if ui.button("Back").clicked() || back_pressed(ui.ctx()) {
+                    leave = true;
+                }
+            });
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui_alignments::top_horizontal(ui, |ui| {
+                ui.heading(RichText::new("JVS Diagnostics").size(35.0).strong());
+            });
+            ui.label(format!("Port: {}", monitor.path.display()));
+            if let Some(e) = &monitor.error {
+                ui.colored_label(Color32::RED, e);
+            }
+            ui.separator();
+            let Some(info) = &monitor.info else {
+                if monitor.error.is_none() {
+                    ui.label("Connecting to the board...");
+                }
+                return;
+            };
+            egui::ScrollArea::vertical()
+                .auto_shrink(false)
+                .show(ui, |ui| {
+                    egui::Grid::new("jvs info grid").show(ui, |ui| {
+                        ui.label("Board ID:");
+                        ui.strong(&info.id);
+                        ui.end_row();
+                        ui.label("Versions:");
+                        ui.label(format!(
+                            "Command {},JVS {},Communication {}",
+                            jvs::bcd_version(info.command_version),
+                            jvs::bcd_version(info.jvs_version),
+                            jvs::bcd_version(info.comms_version)
+                        ));
+                        ui.end_row();
+                        ui.label("Features:");
+                        ui.vertical(|ui| {
+                            for i in info.capabilities.describe() {
+                                ui.label(i);
+                            }
+                        });
+                        ui.end_row();
+                    });
+                    ui.separator();
+                    let Some(inputs) = &monitor.inputs else {
+                        return;
+                    };
+                    let light = |ui: &mut egui::Ui, text: &str, active: bool| {
+                        if active {
+                            ui.colored_label(Color32::from_rgb(0, 200, 0), text);
+                        } else {
+                            ui.weak(text);
+                        }
+                    };
+                    light(ui, "Test", inputs.test());
+                    let caps = &info.capabilities;
+                    for player in 0..caps.players as usize {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.strong(format!("Player {}:", player + 1));
+                            for n in 0..caps.switches as usize {
+                                light(ui, &jvs::switch_name(n), inputs.switch(player, n));
+                            }
+                        });
+                    }
+                    for (slot, coins) in inputs.coins.iter().enumerate() {
+                        ui.label(format!("Coin slot {}: {}", slot + 1, coins));
+                    }
+                    for (channel, value) in inputs.analogs.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Analog {}:", channel + 1));
+                            // Only the top analog_bits bits carry data
+                            let value = *value >> (16 - caps.analog_bits.clamp(1, 16));
+                            ui.add(
+                                egui::ProgressBar::new(
+                                    value as f32
+                                        / ((1u32 << caps.analog_bits.clamp(1, 16)) - 1) as f32,
+                                )
+                                .text(value.to_string()),
+                            );
+                        });
+                    }
+                });
+        });
+        if leave {
+            self.jvs_monitor = None;
+            self.app_state = AppState::ConfigureGame;
+        }
+    }
+}
+
+impl LoaderApp {
+    fn open_link_wizard(&mut self) {
+        let Some(kind) = LinkKind::from_title(&self.current_game) else {
+            return;
+        };
+        // Configs saved without NIC_NAME start on the NIC holding a seat IP
+        if self.shared_state.temp_config.nic_name.is_empty()
+            && let Some(nic) = kind.find_nic(
+                &self.shared_state.temp_config,
+                &self.shared_state.temp_interface,
+            )
+        {
+            self.shared_state.temp_config.nic_name = nic;
+        }
+        let (min, max) = kind.cabinets();
+        let mut seats = kind.seats(&self.shared_state.temp_config);
+        seats.resize(seats.len().clamp(min, max), String::new());
+        self.shared_state.link_seats = seats;
+        self.app_state = AppState::LinkWizard;
+        match Discovery::start_lan() {
+            Err(e) => {
+                self.set_modal(
+                    format!("Unable to look for other launchers:\n{}", e),
+                    ModalStatus::Error,
+                );
+            }
+            Result::Ok(k) => {
+                self.discovery = Some(k);
+            }
+        }
+    }
+    fn link_wizard_ui(&mut self, ctx: &egui::Context) {
+        let Some(kind) = LinkKind::from_title(&self.current_game) else {
+            self.app_state = AppState::ConfigureGame;
+            return;
+        };
+        let subnet = self
+            .shared_state
+            .temp_interface
+            .iter()
+            .find(|x| x.name == self.shared_state.temp_config.nic_name)
+            .and_then(Subnet::of);
+        let plan = subnet.map(|x| LinkPlan::parse(kind, x, &self.shared_state.link_seats));
+        let problems = match &plan {
+            None => vec!["Pick a network card with an IPv4 address".to_string()],
+            Some(Err(e)) => vec![e.to_string()],
+            Some(Result::Ok(x)) => x.problems(),
+        };
+        let plan = plan.and_then(|x| x.ok()).filter(|_| problems.is_empty());
+        let mut result: Option<anyhow::Result<String>> = None;
+        if let Some(discovery) = &mut self.discovery {
+            discovery.update();
+            let ip = subnet.map_or(Ipv4Addr::UNSPECIFIED, |x| x.ip);
+            discovery.announce(
+                ip,
+                &format!("{:?}", self.current_game),
+                self.shared_state
+                    .link_seats
+                    .iter()
+                    .position(|x| x.trim() == ip.to_string())
+                    .map(|x| x + 1),
+                subnet
+                    .map(|x| SocketAddr::from((x.broadcast(), discovery::DISCOVERY_PORT)))
+                    .into_iter()
+                    .collect(),
+            );
+            // Peers come and go without any input
+            ctx.request_repaint_after(Duration::from_millis(500));
+        }
+        egui::TopBottomPanel::bottom("link wizard btm panel").show(ctx, |ui| {
+            egui_alignments::center_horizontal(ui, |ui| {
+                if ui
+                    .add_enabled(plan.is_some(), egui::Button::new("Apply"))
+                    .clicked()
+                    && let Some(plan) = &plan
+                {
+                    plan.apply(
+                        &mut self.shared_state.temp_config,
+                        plan.this_seat().unwrap(),
+                    );
+                    self.app_state = AppState::ConfigureGame;
+                    result = Some(Result::Ok(
+                        "Link settings applied,Save the config to keep them".to_string(),
+                    ));
+                }
+                if ui
+                    .add_enabled(
+                        plan.is_some(),
+                        egui::Button::new("Export for Other Cabinets"),
+                    )
+                    .clicked()
+                    && let Some(plan) = &plan
+                    && let Some(dir) = FileDialog::new().pick_folder()
+                {
+                    result = Some(
+                        plan.export(&self.shared_state.temp_config, &self.current_game, &dir)
+                            .map(|files| {
+                                format!(
+                                    "Copy these to the other cabinets as ./config/{:?}.conf:\n{}",
+                                    self.current_game,
+                                    files
+                                        .iter()
+                                        .map(|x| x.display().to_string())
+                                        .collect::<Vec<_>>()
+                                        .join("\n")
+                                )
+                            }),
+                    );
+                }
+                if ui.button("Back").clicked() || back_pressed(ui.ctx()) {
+                    self.app_state = AppState::ConfigureGame;
+                }
+            });
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui_alignments::top_horizontal(ui, |ui| {
+                ui.heading(RichText::new("Link Setup").size(35.0).strong());
+            });
+            ui.label("Every linked cabinet needs its own IP on the same network.");
+            ui.separator();
+            egui::Grid::new("link wizard grid").show(ui, |ui| {
+                ui.label("Network Card:");
+                egui::ComboBox::from_id_salt("link nic combobox")
+                    .selected_text(&self.shared_state.temp_config.nic_name)
+                    .show_ui(ui, |ui| {
+                        for i in self.shared_state.temp_interface.iter() {
+                            ui.selectable_value(
+                                &mut self.shared_state.temp_config.nic_name,
+                                i.name.clone(),
+                                &i.name,
+                            );
+                        }
+                    });
+                ui.end_row();
+                ui.label("This machine:");
+                ui.label(subnet.map_or("No IPv4 address".to_string(), |x| x.to_string()));
+                ui.end_row();
+                let (min, max) = kind.cabinets();
+                ui.label("Cabinets:");
+                ui.horizontal(|ui| {
+                    let seats = &mut self.shared_state.link_seats;
+                    if ui
+                        .add_enabled(seats.len() > min, egui::Button::new("-"))
+                        .clicked()
+                    {
+                        seats.pop();
+                    }
+                    ui.label(seats.len().to_string());
+                    if ui
+                        .add_enabled(seats.len() < max, egui::Button::new("+"))
+                        .clicked()
+                    {
+                        seats.push(String::new());
+                    }
+                    if let Some(subnet) = subnet
+                        && ui.button("Suggest IPs").clicked()
+                    {
+                        self.shared_state.link_seats = LinkPlan::suggest(kind, subnet, seats.len())
+                            .seats
+                            .iter()
+                            .map(|x| x.to_string())
+                            .collect();
+                    }
+                });
+                ui.end_row();
+                for (cnt, i) in self.shared_state.link_seats.iter_mut().enumerate() {
+                    ui.label(format!("Seat {} IP:", cnt + 1));
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(i);
+                        if subnet.is_some_and(|x| i.trim() == x.ip.to_string()) {
+                            ui.strong("This machine");
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+            ui.separator();
+            for i in &problems {
+                ui.colored_label(Color32::RED, i);
+            }
+            let Some(discovery) = &self.discovery else {
+                return;
+            };
+            ui.separator();
+            let peers = discovery.peers();
+            ui.horizontal(|ui| {
+                ui.strong("Other launchers on the network");
+                if ui
+                    .add_enabled(!peers.is_empty(), egui::Button::new("Fill Seats"))
+                    .on_hover_text("Use the launchers running this game,in the seats they announce")
+                    .clicked()
+                {
+                    let (_, max) = kind.cabinets();
+                    self.shared_state.link_seats = discovery::seat_order(&discovery.me(), &peers)
+                        .iter()
+                        .take(max)
+                        .map(|x| x.to_string())
+                        .collect();
+                }
+            });
+            if peers.is_empty() {
+                ui.label("Looking for other launchers...");
+            }
+            egui::Grid::new("link peers grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    for i in &peers {
+                        let same_game = i.game == format!("{:?}", self.current_game);
+                        let text = |x: String| {
+                            if same_game {
+                                RichText::new(x).strong()
+                            } else {
+                                RichText::new(x).weak()
+                            }
+                        };
+                        ui.label(text(i.name.clone()));
+                        ui.label(text(i.ip.to_string()));
+                        ui.label(text(i.game.replace('_', " ")));
+                        ui.label(text(
+                            i.seat
+                                .map_or("No seat".to_string(), |x| format!("Seat {}", x)),
+                        ));
+                        ui.end_row();
+                    }
+                });
+        });
+        if !matches!(self.app_state, AppState::LinkWizard) {
+            self.discovery = None;
+        }
+        match result {
+            None => {}
+            Some(Err(e)) => {
+                self.set_modal(
+                    format!("Unable to export configs:\n{}", e),
+                    ModalStatus::Error,
+                );
+            }
+            Some(Result::Ok(msg)) => {
+                self.set_modal(msg, ModalStatus::Info);
+            }
+        }
+    }
+    fn card_dir(&self) -> PathBuf {
+        cards::card_dir(&self.shared_state.temp_config.exe_path)
+    }
+    fn open_card_manager(&mut self) {
+        if self.shared_state.temp_config.exe_path.is_empty() {
+            self.set_modal(
+                "Set the executable path first,card files are kept next to it".to_string(),
+                ModalStatus::Error,
+            );
+            return;
+        }
+        if self.refresh_cards() {
+            self.shared_state.card_rename = None;
+            self.app_state = AppState::CardManager;
+        }
+    }
+    fn refresh_cards(&mut self) -> bool {
+        match cards::list_cards(&self.card_dir()) {
+            Err(e) => {
+                self.set_modal(
+                    format!("Unable to list card files:\n{}", e),
+                    ModalStatus::Error,
+                );
+                false
+            }
+            Result::Ok(k) => {
+                self.shared_state.cards = k;
+                true
+            }
+        }
+    }
+    fn card_manager_ui(&mut self, ctx: &egui::Context) {
+        let dir = self.card_dir();
+        // Errors and finished actions,shown after the panels are drawn
+        let mut result: Option<anyhow::Result<String>> = None;
+        egui::TopBottomPanel::bottom("card manager btm panel").show(ctx, |ui| {
+            egui_alignments::center_horizontal(ui, |ui| {
+                if ui.button("Back").clicked() || back_pressed(ui.ctx()) {
+                    self.app_state = AppState::ConfigureGame;
+                }
+            });
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui_alignments::top_horizontal(ui, |ui| {
+                ui.heading(RichText::new("Card Manager").size(35.0).strong());
+            });
+            ui.label(format!("Card files in {}", dir.display()));
+            ui.horizontal(|ui| {
+                ui.label("New card:");
+                ui.text_edit_singleline(&mut self.shared_state.card_name);
+                if ui.button("Create").clicked() {
+                    result = Some(
+                        cards::create_card(&dir, &self.shared_state.card_name)
+                            .map(|x| format!("Created {}", x)),
+                    );
+                    self.shared_state.card_name.clear();
+                }
+                if ui.button("⟳").on_hover_text("Rescan cards").clicked() {
+                    result = Some(Ok(String::new()));
+                }
+            });
+            ui.separator();
+            if self.shared_state.cards.is_empty() {
+                ui.label("No card files yet.");
+            }
+            let assigned = self.shared_state.temp_config.card_file.clone();
+            egui::ScrollArea::vertical()
+                .auto_shrink(false)
+                .show(ui, |ui| {
+                    egui::Grid::new("card grid").striped(true).show(ui, |ui| {
+                        for card in &self.shared_state.cards {
+                            match &mut self.shared_state.card_rename {
+                                Some((old, new)) if *old == card.name => {
+                                    ui.text_edit_singleline(new);
+                                    if ui.button("OK").clicked() {
+                                        result =
+                                            Some(cards::rename_card(&dir, old, new).map(|new| {
+                                                // Keep the players on their card
+                                                for i in self
+                                                    .shared_state
+                                                    .temp_config
+                                                    .card_file
+                                                    .iter_mut()
+                                                {
+                                                    if *i == card.name {
+                                                        *i = new.clone();
+                                                    }
+                                                }
+                                                format!("Renamed {} to {}", card.name, new)
+                                            }));
+                                    }
+                                }
+                                _ => {
+                                    ui.strong(&card.name);
+                                    if ui.button("Rename").clicked() {
+                                        self.shared_state.card_rename =
+                                            Some((card.name.clone(), card.name.clone()));
+                                    }
+                                }
+                            }
+                            ui.label(card.describe());
+                            for player in 0..2 {
+                                let other = &assigned[1 - player];
+                                let selected = assigned[player] == card.name;
+                                let response = ui
+                                    .add_enabled(
+                                        *other != card.name,
+                                        egui::SelectableLabel::new(
+                                            selected,
+                                            format!("Player {}", player + 1),
+                                        ),
+                                    )
+                                    .on_disabled_hover_text("Used by the other player");
+                                if response.clicked() {
+                                    self.shared_state.temp_config.card_file[player] =
+                                        card.name.clone();
+                                }
+                            }
+                            if ui.button("Backup").clicked() {
+                                result = Some(
+                                    cards::backup_card(card)
+                                        .map(|x| format!("Saved a copy to {}", x.display())),
+                                );
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+        });
+        match result {
+            None => {}
+            Some(Err(e)) => {
+                self.set_modal(format!("{}", e), ModalStatus::Error);
+            }
+            Some(Result::Ok(msg)) => {
+                self.shared_state.card_rename = None;
+                if self.refresh_cards() && !msg.is_empty() {
+                    self.set_modal(msg, ModalStatus::Info);
+                }
+            }
+        }
+    }
+    fn open_serial_console(&mut self, kind: BoardKind, path: String) {
+        match SerialConsole::open(Path::new(&path), serial::BOARD_BAUD) {
+            Err(e) => {
+                self.set_modal(
+                    format!("Unable to open the serial port:\n{}", e),
+                    ModalStatus::Error,
+                );
+            }
+            Result::Ok(console) => {
+                self.serial_console = Some((kind, console));
+                self.console_input.clear();
+                self.app_state = AppState::SerialConsole;
+            }
+        }
+    }
+    fn serial_console_ui(&mut self, ctx: &egui::Context) {
+        let Some((kind, console)) = &mut self.serial_console else {
+            self.app_state = AppState::ConfigureGame;
+            return;
+        };
+        console.update();
+        // Replies are read on another thread
+        ctx.request_repaint_after(Duration::from_millis(50));
+        let mut leave = false;
+        let mut error = None;
+        egui::TopBottomPanel::bottom("serial console btm panel").show(ctx, |ui| {
+            egui_alignments::center_horizontal(ui, |ui| {
+                if ui.button("Clear Log").clicked() {
+                    console.log.clear();
+                }
+                if ui.button("Back").clicked() || back_pressed(ui.ctx()) {
+                    leave = true;
+                }
+            });
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui_alignments::top_horizontal(ui, |ui| {
+                ui.heading(RichText::new(format!("Test {}", kind)).size(35.0).strong());
+            });
+            ui.horizontal(|ui| {
+                ui.label(format!("Port: {}", console.path.display()));
+                let mut baud = console.baud;
+                egui::ComboBox::from_id_salt("console baud combobox")
+                    .selected_text(format!("{} baud", baud))
+                    .show_ui(ui, |ui| {
+                        for i in serial::BAUD_RATES {
+                            ui.selectable_value(&mut baud, i, i.to_string());
+                        }
+                    });
+                if baud != console.baud {
+                    match SerialConsole::open(&console.path.clone(), baud) {
+                        Err(e) => error = Some(e),
+                        Result::Ok(mut x) => {
+                            x.log = std::mem::take(&mut console.log);
+                            *console = x;
+                        }
+                    }
+                }
+            });
+            ui.label("Bytes are sent to the board as they are,motors may move.");
+            ui.horizontal(|ui| {
+                ui.label("Bytes (hex):");
+                let edit = ui.text_edit_singleline(&mut self.console_input);
+                let send = ui.button("Send").clicked()
+                    || (edit.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)));
+                if send
+                    && let Err(e) =
+                        serial::parse_hex(&self.console_input).and_then(|x| console.send(&x))
+                {
+                    error = Some(e);
+                }
+            });
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .auto_shrink(false)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for i in &console.log {
+                        let color = match i.direction {
+                            Direction::Sent => ui.visuals().text_color(),
+                            Direction::Received => Color32::from_rgb(0, 200, 0),
+                        };
+                        ui.label(RichText::new(i.to_string()).monospace().color(color));
+                    }
+                });
+        });
+        if let Some(e) = error {
+            self.set_modal(format!("Serial console error:\n{}", e), ModalStatus::Error);
+        }
+        if leave {
+            self.serial_console = None;
+            self.app_state = AppState::ConfigureGame;
+        }
+    }
+    fn refresh_displays(&mut self) {
+        match display::list_outputs() {
+            Err(e) => {
+                self.shared_state.displays = vec![];
+                self.shared_state.display_error = Some(e.to_string());
+            }
+            Result::Ok(outputs) => {
+                self.shared_state.displays = outputs;
+                self.shared_state.display_error = None;
+            }
+        }
+    }
+    // Output and mode rows of the configure game grid
+    fn display_picker(&mut self, ui: &mut egui::Ui) {
+        let target = &mut self.shared_state.temp_config.display;
+        let mut refresh = false;
+        ui.label("Display:");
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("display output combobox")
+                .selected_text(if target.output.is_empty() {
+                    "Default"
+                } else {
+                    target.output.as_str()
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(target, DisplayTarget::default(), "Default");
+                    for i in &self.shared_state.displays {
+                        let text = if i.primary {
+                            format!("{} (primary)", i.name)
+                        } else {
+                            i.name.clone()
+                        };
+                        if ui.selectable_label(target.output == i.name, text).clicked()
+                            && target.output != i.name
+                        {
+                            *target = DisplayTarget {
+                                output: i.name.clone(),
+                                mode: None,
+                            };
+                        }
+                    }
+                });
+            refresh = ui.button("⟳").on_hover_text("Rescan displays").clicked();
+            if let Some(e) = &self.shared_state.display_error {
+                ui.colored_label(Color32::from_rgb(220, 150, 0), e);
+            } else if !target.output.is_empty()
+                && !self
+                    .shared_state
+                    .displays
+                    .iter()
+                    .any(|x| x.name == target.output)
+            {
+                ui.colored_label(Color32::from_rgb(220, 150, 0), "Not connected");
+            }
+        });
+        ui.end_row();
+        if let Some(output) = self
+            .shared_state
+            .displays
+            .iter()
+            .find(|x| x.name == target.output)
+        {
+            ui.label("Display Mode:");
+            egui::ComboBox::from_id_salt("display mode combobox")
+                .selected_text(
+                    target
+                        .mode
+                        .map_or("Keep current".to_string(), |x| x.to_string()),
+                )
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut target.mode, None, "Keep current");
+                    for i in &output.modes {
+                        let text = if output.current == Some(*i) {
+                            format!("{} (current)", i)
+                        } else {
+                            i.to_string()
+                        };
+                        ui.selectable_value(&mut target.mode, Some(*i), text);
+                    }
+                });
+            ui.end_row();
+        }
+        if refresh {
+            self.refresh_displays();
+        }
+    }
+    // Row under GPU Vendor in the configure game grid
+    fn gpu_recommendation(&mut self, ui: &mut egui::Ui) {
+        ui.label("");
+        ui.horizontal(|ui| {
+            let Some(gpu) = &self.gpu else {
+                ui.weak("Detecting...");
+                return;
+            };
+            ui.weak(format!("Detected: {}", gpu.describe()));
+            if let Some(vendor) = gpu.recommend()
+                && vendor != self.shared_state.temp_config.gpu_vendor
+                && ui
+                    .button(format!("Use {}", vendor))
+                    .on_hover_text("Recommended for this GPU")
+                    .clicked()
+            {
+                self.shared_state.temp_config.gpu_vendor = vendor;
+            }
+        });
+        ui.end_row();
+    }
+    // Native resolution of the display the game goes to,or of the one the GUI is on
+    fn screen_size(&self, ctx: &egui::Context) -> Option<Resolution> {
+        let displays = &self.shared_state.displays;
+        let target = &self.shared_state.temp_config.display;
+        let output = displays
+            .iter()
+            .find(|x| x.name == target.output)
+            .or_else(|| displays.iter().find(|x| x.primary))
+            .or(displays.first());
+        if let Some(mode) = output.and_then(|x| {
+            target
+                .mode
+                .filter(|_| x.name == target.output)
+                .or(x.preferred)
+                .or(x.current)
+        }) {
+            return Some(Resolution::new(mode.width, mode.height));
+        }
+        ctx.input(|i| {
+            let size = i.viewport().monitor_size? * i.viewport().native_pixels_per_point?;
+            Some(Resolution::new(
+                size.x.round() as u32,
+                size.y.round() as u32,
+            ))
+        })
+    }
+    // Window size rows of the configure game grid
+    fn resolution_editor(&mut self, ui: &mut egui::Ui) {
+        let native = resolution::native_resolution(&self.current_game);
+        let screen = self.screen_size(ui.ctx());
+        let config = &mut self.shared_state.temp_config;
+        ui.label("Window Size:");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut config.window_size.0).range(1..=7680));
+            ui.label("x");
+            ui.add(egui::DragValue::new(&mut config.window_size.1).range(1..=4320));
+            ui.weak(Resolution::from(config.window_size).aspect());
+        });
+        ui.end_row();
+        ui.label("Presets:");
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("window size combobox")
+                .selected_text(Resolution::from(config.window_size).to_string())
+                .show_ui(ui, |ui| {
+                    for (group, sizes) in resolution::PRESETS {
+                        ui.strong(group);
+                        for i in sizes {
+                            ui.selectable_value(
+                                &mut config.window_size,
+                                (*i).into(),
+                                i.to_string(),
+                            );
+                        }
+                        ui.separator();
+                    }
+                });
+            if let Some(screen) = screen
+                && ui
+                    .button(format!("Screen Size ({})", screen))
+                    .on_hover_text("The native resolution of the display")
+                    .clicked()
+            {
+                config.window_size = screen.into();
+            }
+        });
+        ui.end_row();
+        ui.label("Whole Scales:");
+        ui.horizontal_wrapped(|ui| {
+            for (scale, size) in resolution::integer_scales(native, screen) {
+                ui.selectable_value(
+                    &mut config.window_size,
+                    size.into(),
+                    format!("{}x ({})", scale, size),
+                )
+                .on_hover_text(format!("The game renders at {}", native));
+            }
+        });
+        ui.end_row();
+        let check = SizeCheck::run(config, native, screen);
+        if let Some((picture, scale)) = check.picture {
+            ui.label("Game Picture:");
+            ui.label(format!("{} ({:.2}x of {})", picture, scale, native));
+            ui.end_row();
+        }
+        for (text, color) in check
+            .errors
+            .iter()
+            .map(|x| (x, Color32::from_rgb(255, 0, 0)))
+            .chain(
+                check
+                    .warnings
+                    .iter()
+                    .map(|x| (x, Color32::from_rgb(220, 150, 0))),
+            )
+        {
+            ui.label("");
+            ui.colored_label(color, text);
+            ui.end_row();
+        }
+    }
+    fn refresh_serial_ports(&mut self) {
+        match serial::list_ports() {
+            Err(e) => {
+                self.set_modal(
+                    format!("Unable to list serial ports:\n{}", e),
+                    ModalStatus::Error,
+                );
+            }
+            Result::Ok(ports) => {
+                self.shared_state.serial_ports = ports;
+            }
+        }
+    }
+    // Free text,with the ports found on this machine one click away
+    fn serial_port_picker(
+        &mut self,
+        ui: &mut egui::Ui,
+        salt: &str,
+        field: impl Fn(&mut LindberghConfig) -> &mut String,
+    ) {
+        let mut refresh = false;
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                let value = field(&mut self.shared_state.temp_config);
+                ui.text_edit_singleline(value);
+                egui::ComboBox::from_id_salt(salt)
+                    .selected_text("Detected")
+                    .show_ui(ui, |ui| {
+                        if self.shared_state.serial_ports.is_empty() {
+                            ui.weak("No serial ports found");
+                        }
+                        for port in &self.shared_state.serial_ports {
+                            for path in port.paths() {
+                                let path = path.to_string_lossy().to_string();
+                                ui.selectable_value(
+                                    value,
+                                    path.clone(),
+                                    format!("{}  {}", path, port.description()),
+                                );
+                            }
+                        }
+                    });
+                refresh = ui.button("⟳").on_hover_text("Rescan ports").clicked();
+            });
+            let value = field(&mut self.shared_state.temp_config);
+            let port = self
+                .shared_state
+                .serial_ports
+                .iter()
+                .find(|x| x.paths().any(|x| *x == Path::new(value.as_str())));
+            // Checked when the ports are scanned,not every frame
+            let problem = match port {
+                Some(port) => port.problem.clone(),
+                None if value.is_empty() => None,
+                None => Some(format!(
+                    "{} was not found when the ports were scanned",
+                    value
+                )),
+            };
+            if let Some(problem) = problem {
+                ui.colored_label(Color32::from_rgb(220, 150, 0), problem);
+            }
+        });
+        if refresh {
+            self.refresh_serial_ports();
+        }
+    }
+    fn start_input_test(&mut self) {
+        self.input_test = true;
+        let Some(evdev) = self.shared_state.temp_config.input_method.get_evdev() else {
+            return;
+        };
+        let mut bindings = Vec::new();
+        for keymap in [&evdev.player1, &evdev.player2] {
+            bindings.extend(
+                KeymapField::ALL
+                    .iter()
+                    .filter_map(|x| keymap.get(*x).cloned()),
+            );
+        }
+        bindings.extend(evdev.analogues.iter().cloned());
+        self.input_monitor = Some(InputMonitor::start(
+            &self.shared_state.evdev_devices,
+            &bindings,
+        ));
+    }
+    // Lights up every mapped input while its key is held
+    fn input_tester_ui(&mut self, ctx: &egui::Context) {
+        if !self.input_test {
+            return;
+        }
+        if let Some(m) = &mut self.input_monitor {
+            m.update();
+        }
+        // Evdev events don't come from egui
+        ctx.request_repaint_after(Duration::from_millis(50));
+        let template = InputTemplate::from(&self.current_game);
+        let keymap = &self.shared_state.temp_config.input_method;
+        let (held_keys, modifiers) = ctx.input(|i| (i.keys_down.clone(), i.modifiers));
+        let sdl_active = |key: &SdlKey| match key {
+            SdlKey::Key(k) => held_keys.contains(k),
+            SdlKey::Raw(KEYCODE_SHIFT) => modifiers.shift,
+            SdlKey::Raw(KEYCODE_CONTROL) => modifiers.ctrl,
+            SdlKey::Raw(KEYCODE_ALT) => modifiers.alt,
+            SdlKey::Raw(_) => false,
+        };
+        let light = |ui: &mut egui::Ui, bound: bool, active: bool| {
+            if !bound {
+                ui.label("");
+            } else if active {
+                ui.colored_label(Color32::from_rgb(0, 200, 0), "⏺");
+            } else {
+                ui.weak("⏺");
+            }
+        };
+        egui::SidePanel::right("input tester panel").show(ctx, |ui| {
+            ui.heading("Input Tester");
+            ui.label("Hold the keys to see which inputs they trigger.");
+            if let Some(m) = &self.input_monitor {
+                for i in &m.errors {
+                    ui.colored_label(Color32::from_rgb(255, 0, 0), i);
+                }
+            }
+            ui.separator();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("input tester grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.strong("SDL");
+                        ui.strong("Evdev");
+                        ui.end_row();
+                        for player in 1..=2 {
+                            for field in template.fields(player) {
+                                if field == KeymapField::Test {
+                                    ui.label("Test");
+                                } else {
+                                    ui.label(format!(
+                                        "P{} {}",
+                                        player,
+                                        template.label(field).unwrap()
+                                    ));
+                                }
+                                let sdl = keymap.get_sdl().and_then(|x| {
+                                    if player == 1 { &x.player1 } else { &x.player2 }.get(field)
+                                });
+                                light(ui, sdl.is_some(), sdl.is_some_and(sdl_active));
+                                let evdev = keymap.get_evdev().and_then(|x| {
+                                    if player == 1 { &x.player1 } else { &x.player2 }.get(field)
+                                });
+                                light(
+                                    ui,
+                                    evdev.is_some(),
+                                    evdev.is_some_and(|x| {
+                                        self.input_monitor.as_ref().is_some_and(|m| m.is_held(x))
+                                    }),
+                                );
+                                ui.end_row();
+                            }
+                        }
+                    });
+                if let Some(evdev) = keymap.get_evdev() {
+                    ui.separator();
+                    for (channel, axis) in evdev.analogues.iter().enumerate() {
+                        let state = self.input_monitor.as_ref().and_then(|m| m.axes.get(axis));
+                        ui.label(template.analogue_label(channel));
+                        match state {
+                            Some(x) if x.max > x.min => {
+                                ui.add(
+                                    egui::ProgressBar::new(
+                                        (x.value - x.min) as f32 / (x.max - x.min) as f32,
+                                    )
+                                    .text(x.value.to_string()),
+                                );
+                            }
+                            _ => {
+                                ui.weak("Move the axis end to end");
+                            }
+                        }
+                    }
+                }
+            });
+        });
+    }
+    fn refresh_presets(&mut self) {
+        match presets::list_presets() {
+            Err(e) => {
+                self.set_modal(
+                    format!("Unable to list keymap presets:\n{}", e),
+                    ModalStatus::Error,
+                );
+            }
+            Result::Ok(list) => {
+                self.shared_state.presets = list;
+            }
+        }
+    }
+    fn presets_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Preset");
+            egui::ComboBox::from_id_salt("preset combobox")
+                .selected_text(
+                    self.shared_state
+                        .selected_preset
+                        .clone()
+                        .unwrap_or("None".into()),
+                )
+                .show_ui(ui, |ui| {
+                    for i in self.shared_state.presets.iter() {
+                        ui.selectable_value(
+                            &mut self.shared_state.selected_preset,
+                            Some(i.clone()),
+                            i,
+                        );
+                    }
+                });
+            let game_type = self.current_game.as_gamedata().game_type;
+            if let Some(name) = self.shared_state.selected_preset.clone() {
+                if ui.button("Apply").clicked() {
+                    match presets::load_preset(&name) {
+                        Err(e) => self.set_modal(
+                            format!("Unable to load preset {}:\n{}", name, e),
+                            ModalStatus::Error,
+                        ),
+                        Result::Ok(keymap) => {
+                            self.shared_state.temp_config.input_method = keymap;
+                        }
+                    }
+                }
+                if let Some(t) = &game_type
+                    && ui
+                        .button(format!("Apply to all {:?} games", t))
+                        .on_hover_text("Saved configs of the other games in the library are updated right away")
+                        .clicked()
+                {
+                    self.apply_preset_to_type(&name);
+                }
+                if ui.button("Export").clicked()
+                    && let Some(path) = FileDialog::new()
+                        .add_filter("Keymap Preset(*.preset)", &["preset"])
+                        .set_file_name(format!("{}.preset", name))
+                        .save_file()
+                    && let Err(e) = presets::preset_path(&name)
+                        .and_then(|x| fs::copy(x, &path).map_err(|e| e.into()))
+                {
+                    self.set_modal(
+                        format!("Unable to export preset {}:\n{}", name, e),
+                        ModalStatus::Error,
+                    );
+                }
+            }
+            if ui.button("Import").clicked()
+                && let Some(path) = FileDialog::new()
+                    .add_filter("Keymap Preset(*.preset)", &["preset"])
+                    .pick_file()
+            {
+                match presets::import_preset(&path) {
+                    Err(e) => self.set_modal(
+                        format!(
+                            "Unable to import {}:\n{}",
+                            path.to_string_lossy(),
+                            e
+                        ),
+                        ModalStatus::Error,
+                    ),
+                    Result::Ok(name) => {
+                        self.refresh_presets();
+                        self.shared_state.selected_preset = Some(name);
+                    }
+                }
+            }
+            ui.separator();
+            ui.add(
+                egui::TextEdit::singleline(&mut self.shared_state.preset_name)
+                    .hint_text("Preset name")
+                    .desired_width(120.0),
+            );
+            if ui.button("Save as Preset").clicked() {
+                let name = self.shared_state.preset_name.trim().to_string();
+                if let Err(e) =
+                    presets::save_preset(&name, &self.shared_state.temp_config.input_method)
+                {
+                    self.set_modal(
+                        format!("Unable to save preset:\n{}", e),
+                        ModalStatus::Error,
+                    );
+                } else {
+                    self.refresh_presets();
+                    self.shared_state.selected_preset = Some(name);
+                    self.shared_state.preset_name.clear();
+                }
+            }
+        });
+    }
+    fn apply_preset_to_type(&mut self, name: &str) {
+        let keymap = match presets::load_preset(name) {
+            Err(e) => {
+                self.set_modal(
+                    format!("Unable to load preset {}:\n{}", name, e),
+                    ModalStatus::Error,
+                );
+                return;
+            }
+            Result::Ok(k) => k,
+        };
+        let game_type = self.current_game.as_gamedata().game_type;
+        let mut applied = Vec::new();
+        let mut errors = Vec::new();
+        for title in self
+            .game_library
+            .iter()
+            .filter(|x| x.game_type == game_type)
+            .map(GameTitle::from)
+        {
+            // The current game is applied to the page,and saved with the Save button
+            if title == self.current_game {
+                continue;
+            }
+            if !fs::exists(format!("./config/{:?}.conf", title)).unwrap_or(false) {
+                continue;
+            }
+            match presets::apply_to_game(&keymap, &title)
+                .and_then(|_| revisions::save_revision(&title))
+            {
+                Err(e) => errors.push(format!("{}: {}", title, e)),
+                Result::Ok(_) => applied.push(title.to_string()),
+            }
+        }
+        self.shared_state.temp_config.input_method = keymap;
+        if errors.is_empty() {
+            self.set_modal(
+                format!(
+                    "Preset {} applied to this game and:\n{}",
+                    name,
+                    applied.join("\n")
+                ),
+                ModalStatus::Info,
+            );
+        } else {
+            self.set_modal(
+                format!(
+                    "Preset {} could not be applied to:\n{}",
+                    name,
+                    errors.join("\n")
+                ),
+                ModalStatus::Error,
+            );
+        }
+    }
+    fn conf_path(&self) -> String {
+        format!("./config/{:?}.conf", self.current_game)
+    }
+    // What is currently on disk,or what a fresh config would look like if nothing was saved yet
+    fn saved_conf_string(&self) -> String {
+        fs::read_to_string(self.conf_path()).unwrap_or_else(|_| {
+            LindberghConfig::default()
+                .to_lindbergh_conf_string(&self.current_game)
+                .unwrap_or_default()
+        })
+    }
+    fn pending_conf_string(&self) -> anyhow::Result<String> {
+        let mut config = self.shared_state.temp_config.clone();
+        if matches!(self.app_state, AppState::ConfigureGame) {
+            self.shared_state
+                .assign_conf(&mut config, &self.current_game)?;
+        }
+        config.to_lindbergh_conf_string(&self.current_game)
+    }
+    // Recomputed only when the game or its config changes
+    fn unsupported_keys(&mut self) -> &[String] {
+        let stale = self
+            .unsupported_keys
+            .as_ref()
+            .is_none_or(|(title, config, _)| {
+                *title != self.current_game || *config != self.shared_state.temp_config
+            });
+        if stale {
+            let keys = match (
+                &self.loader_info,
+                self.shared_state
+                    .temp_config
+                    .to_lindbergh_conf_string(&self.current_game),
+            ) {
+                (Some(info), Result::Ok(s)) => info.unsupported_keys(&s),
+                _ => vec![],
+            };
+            self.unsupported_keys = Some((
+                self.current_game.clone(),
+                self.shared_state.temp_config.clone(),
+                keys,
+            ));
+        }
+        &self.unsupported_keys.as_ref().unwrap().2
+    }
+    fn unsupported_keys_warning(&mut self, ui: &mut egui::Ui) {
+        let keys = self.unsupported_keys();
+        if !keys.is_empty() {
+            ui.colored_label(
+                Color32::from_rgb(255, 255, 0),
+                format!(
+                    "The installed lindbergh-loader does not seem to support these,they are hidden but still written with their current values: {}",
+                    keys.join(", ")
+                ),
+            );
+        }
+    }
+    // Options the installed loader doesn't know are hidden,they keep their current values
+    fn option_shown(&self, key: &str) -> bool {
+        self.loader_info.as_ref().is_none_or(|x| x.supports(key))
+    }
+    fn key_support_text(&self, key: &str) -> &'static str {
+        match &self.loader_info {
+            Some(info) if !info.supports(key) => "Not supported by the installed lindbergh-loader",
+            _ => "Supported by the installed lindbergh-loader",
+        }
+    }
+    fn has_unsaved_changes(&self) -> bool {
+        match self.pending_conf_string() {
+            Result::Ok(s) => !diff_conf(&self.saved_conf_string(), &s).is_empty(),
+            Err(_) => true,
+        }
+    }
+    fn preview_changes(&mut self) {
+        match self
+            .shared_state
+            .temp_config
+            .to_lindbergh_conf_string(&self.current_game)
+        {
+            Err(e) => {
+                self.set_modal(
+                    format!("Error occurred while writing data \"{}\"", e),
+                    ModalStatus::Error,
+                );
+            }
+            Result::Ok(s) => {
+                self.shared_state.pending_changes = Some(diff_conf(&self.saved_conf_string(), &s));
+            }
+        }
+    }
+    fn save_temp_config(&mut self) {
+        if let Err(e) = self
+            .shared_state
+            .temp_config
+            .write_to_lindbergh_conf(&self.current_game)
+        {
+            self.set_modal(
+                format!("Error occurred while writing data \"{}\"", e),
+                ModalStatus::Error,
+            );
+        } else if let Err(e) = revisions::save_revision(&self.current_game) {
+            self.set_modal(
+                format!(
+                    "Configuration saved,but unable to keep a revision of it:\n{}",
+                    e
+                ),
+                ModalStatus::Error,
+            );
+            *self.get_config_mut() = self.shared_state.temp_config.clone();
+            self.leave_configure_page();
+        } else {
+            *self.get_config_mut() = self.shared_state.temp_config.clone();
+            self.set_modal(
+                format!("Configuration successfully saved into {}", self.conf_path()),
+                ModalStatus::Info,
+            );
+            self.leave_configure_page();
+        }
+    }
+    fn try_leave_configure_page(&mut self) {
+        if self.has_unsaved_changes() {
+            self.shared_state.leave_warning = true;
+        } else {
+            self.leave_configure_page();
+        }
+    }
+    fn leave_configure_page(&mut self) {
+        self.evdev_capture = None;
+        self.axis_monitor = None;
+        self.axis_capture = None;
+        self.input_test = false;
+        self.input_monitor = None;
+        self.app_state = AppState::MainPage;
+        self.shared_state = SharedState::default();
+    }
+    fn config_changes_modal(&mut self, ctx: &egui::Context) {
+        if let Some(changes) = self.shared_state.pending_changes.clone() {
+            let modal = Modal::new(egui::Id::new("Changes Modal")).show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(RichText::new("Review Changes").strong().size(25.0));
+                });
+                ui.separator();
+                ui.label(format!("Compared with {}:", self.conf_path()));
+                if changes.is_empty() {
+                    ui.label("Nothing has changed.");
+                }
+                let conflicts = self.shared_state.temp_config.input_method.conflicts();
+                if !conflicts.is_empty() {
+                    ui.colored_label(
+                        Color32::from_rgb(255, 0, 0),
+                        "Some inputs share the same key:",
+                    );
+                    for (a, b) in conflicts {
+                        ui.colored_label(Color32::from_rgb(255, 0, 0), format!("{} / {}", a, b));
+                    }
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("changes grid")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for i in changes.iter() {
+                                    match i {
+                                        ConfChange::Added { key, value } => {
+                                            ui.colored_label(Color32::from_rgb(0, 128, 0), key)
+                                                .on_hover_text(self.key_support_text(key));
+                                            ui.label("");
+                                            ui.colored_label(Color32::from_rgb(0, 128, 0), value);
+                                        }
+                                        ConfChange::Removed { key, value } => {
+                                            ui.colored_label(Color32::from_rgb(255, 0, 0), key);
+                                            ui.colored_label(Color32::from_rgb(255, 0, 0), value);
+                                            ui.label("");
+                                        }
+                                        ConfChange::Modified { key, old, new } => {
+                                            ui.strong(key)
+                                                .on_hover_text(self.key_support_text(key));
+                                            ui.colored_label(Color32::from_rgb(255, 0, 0), old);
+                                            ui.colored_label(Color32::from_rgb(0, 128, 0), new);
+                                        }
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                ui.separator();
+                egui_alignments::center_horizontal(ui, |ui| {
+                    let save = ui.button("Save");
+                    focus_on_open(ui, &save);
+                    if save.clicked() {
+                        self.shared_state.pending_changes = None;
+                        self.save_temp_config();
+                    }
+                    if ui.button("Discard").clicked() {
+                        self.leave_configure_page();
+                    }
+                    if ui.button("Back").clicked() {
+                        self.shared_state.pending_changes = None;
+                    }
+                });
+            });
+            if modal.should_close() {
+                self.shared_state.pending_changes = None;
+            }
+        }
+        if self.shared_state.leave_warning {
+            let modal = Modal::new(egui::Id::new("Leave Modal")).show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.colored_label(
+                        Color32::from_rgb(255, 255, 0),
+                        RichText::new("Warning").strong().size(25.0),
+                    );
+                });
+                ui.separator();
+                ui.label("You have unsaved changes,leave this page anyway?");
+                egui_alignments::center_horizontal(ui, |ui| {
+                    if ui.button("Leave without saving").clicked() {
+                        self.leave_configure_page();
+                    }
+                    let stay = ui.button("Stay");
+                    focus_on_open(ui, &stay);
+                    if stay.clicked() {
+                        self.shared_state.leave_warning = false;
+                    }
+                });
+            });
+            if modal.should_close() {
+                self.shared_state.leave_warning = false;
+            }
+        }
+    }
+}
+
+impl LoaderApp {
+    fn capture_button(&mut self, ui: &mut egui::Ui, target: CaptureTarget) {
+        let capturing = self.evdev_capture.as_ref().is_some_and(|x| x.0 == target);
+        if ui
+            .button(if capturing { "⏳" } else { "🎯" })
+            .on_hover_text("Press to bind")
+            .clicked()
+        {
+            if capturing {
+                self.evdev_capture = None;
+            } else {
+                self.start_evdev_capture(target);
+            }
+        }
+    }
+    fn axis_capture_button(&mut self, ui: &mut egui::Ui, channel: usize) {
+        let capturing = self.axis_capture == Some(channel);
+        if ui
+            .button(if capturing { "⏳" } else { "🎯" })
+            .on_hover_text("Press to bind")
+            .clicked()
+        {
+            if capturing {
+                self.axis_capture = None;
+            } else {
+                self.start_axis_capture(channel);
+            }
+        }
+    }
+    fn start_evdev_capture(&mut self, target: CaptureTarget) {
+        let Some(device) = self
+            .shared_state
+            .evdev_device
+            .and_then(|x| self.shared_state.evdev_devices.get(x))
+        else {
+            self.set_modal(
+                "Select a capture device first,or type the key name manually.",
+                ModalStatus::Error,
+            );
+            return;
+        };
+        match EventCapture::start(device) {
+            Err(e) => {
+                self.set_modal(e.to_string(), ModalStatus::Error);
+            }
+            Result::Ok(c) => {
+                self.evdev_capture = Some((target, c));
+            }
+        }
+    }
+    fn fill_gun_from_device(&mut self, player: usize) {
+        let Some(device) = self
+            .shared_state
+            .evdev_device
+            .and_then(|x| self.shared_state.evdev_devices.get(x))
+        else {
+            self.set_modal("Select a lightgun device first.", ModalStatus::Error);
+            return;
+        };
+        let name = device.loader_name();
+        let Some(evdev) = self.shared_state.temp_config.input_method.get_evdev_mut() else {
+            return;
+        };
+        evdev.analogues[(player - 1) * 2] = format!("{}_ABS_X", name);
+        evdev.analogues[(player - 1) * 2 + 1] = format!("{}_ABS_Y", name);
+        let keymap = if player == 1 {
+            &mut evdev.player1
+        } else {
+            &mut evdev.player2
+        };
+        keymap.button1 = format!("{}_BTN_LEFT", name);
+        keymap.button2 = format!("{}_BTN_RIGHT", name);
+    }
+    fn start_axis_monitor(&mut self) -> bool {
+        let Some(device) = self
+            .shared_state
+            .evdev_device
+            .and_then(|x| self.shared_state.evdev_devices.get(x))
+        else {
+            self.set_modal(
+                "Select a capture device first,or type the axis name manually.",
+                ModalStatus::Error,
+            );
+            return false;
+        };
+        match EventCapture::start(device) {
+            Err(e) => {
+                self.set_modal(e.to_string(), ModalStatus::Error);
+                false
+            }
+            Result::Ok(c) => {
+                self.axis_monitor = Some((c, AxisReadout::default()));
+                true
+            }
+        }
+    }
+    fn start_axis_capture(&mut self, channel: usize) {
+        if self.axis_monitor.is_none() && !self.start_axis_monitor() {
+            return;
+        }
+        // Only count movement from now on
+        if let Some((_, readout)) = &mut self.axis_monitor {
+            readout.clear();
+        }
+        self.axis_capture = Some(channel);
+    }
+    fn axis_monitor_update(&mut self, ctx: &egui::Context) {
+        let Some((capture, readout)) = &mut self.axis_monitor else {
+            return;
+        };
+        readout.update(&capture.device, &capture.poll());
+        if let Some(channel) = self.axis_capture
+            && let Some(axis) = readout.most_moved(AXIS_CAPTURE_THRESHOLD)
+        {
+            if let Some(evdev) = self.shared_state.temp_config.input_method.get_evdev_mut() {
+                evdev.analogues[channel] = axis;
+            }
+            self.axis_capture = None;
+        }
+        ctx.request_repaint_after(Duration::from_millis(50));
+    }
+    fn evdev_capture_update(&mut self, ctx: &egui::Context) {
+        let Some((target, capture)) = &self.evdev_capture else {
+            return;
+        };
+        let target = *target;
+        if let Some(binding) = capture.poll_binding() {
+            if let Some(evdev) = self.shared_state.temp_config.input_method.get_evdev_mut() {
+                match target {
+                    CaptureTarget::Key(1, field) => evdev.player1.set(field, binding),
+                    CaptureTarget::Key(_, field) => evdev.player2.set(field, binding),
+                }
+            }
+            self.evdev_capture = None;
+        } else {
+            // Events don't come from egui,so keep polling
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+    }
+}
+
+impl LoaderApp {
+    // The config with the text boxes applied,as the history records it
+    fn edited_config(&self) -> LindberghConfig {
+        let mut config = self.shared_state.temp_config.clone();
+        self.shared_state.apply_text(&mut config);
+        config
+    }
+    fn undo(&mut self) {
+        let mut config = self.edited_config();
+        if self.shared_state.history.undo(&mut config) {
+            self.shared_state.temp_config = config;
+            // let the text boxes pick up the restored values
+            self.shared_state.first_run[2] = true;
+        }
+    }
+    fn redo(&mut self) {
+        let mut config = self.edited_config();
+        if self.shared_state.history.redo(&mut config) {
+            self.shared_state.temp_config = config;
+            self.shared_state.first_run[2] = true;
+        }
+    }
+    fn history_update(&mut self, ctx: &egui::Context) {
+        // Text boxes keep their own undo,and what is typed is recorded once they lose focus
+        let editing_text = ctx
+            .memory(|m| m.focused())
+            .is_some_and(|id| egui::TextEdit::load_state(ctx, id).is_some());
+        if editing_text {
+            return;
+        }
+        // Ctrl+Shift+Z has to be checked first,otherwise Ctrl+Z would consume it
+        if ctx.input_mut(|i| {
+            i.consume_shortcut(&KeyboardShortcut::new(
+                Modifiers::COMMAND | Modifiers::SHIFT,
+                Key::Z,
+            ))
+        }) {
+            self.redo();
+        } else if ctx
+            .input_mut(|i| i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Z)))
+        {
+            self.undo();
+        }
+        let config = self.edited_config();
+        self.shared_state.history.record(&config);
+    }
+    fn history_buttons(&mut self, ui: &mut egui::Ui) {
+        ui.separator();
+        if ui
+            .add_enabled(
+                self.shared_state.history.can_undo(),
+                egui::Button::new("⟲ Undo"),
+            )
+            .on_hover_text("Ctrl+Z")
+            .clicked()
+        {
+            self.undo();
+        }
+        if ui
+            .add_enabled(
+                self.shared_state.history.can_redo(),
+                egui::Button::new("⟳ Redo"),
+            )
+            .on_hover_text("Ctrl+Shift+Z")
+            .clicked()
+        {
+            self.redo();
+        }
+        if ui.button("History").clicked() {
+            match revisions::list_revisions(&self.current_game) {
+                Err(e) => {
+                    self.set_modal(
+                        format!("Unable to list saved revisions:\n{}", e),
+                        ModalStatus::Error,
+                    );
+                }
+                Result::Ok(list) => {
+                    self.shared_state.revisions = Some(list);
+                }
+            }
+        }
+    }
+    fn revisions_modal(&mut self, ctx: &egui::Context) {
+        if let Some(list) = self.shared_state.revisions.clone() {
+            let modal = Modal::new(egui::Id::new("Revisions Modal")).show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(RichText::new("Saved Revisions").strong().size(25.0));
+                });
+                ui.separator();
+                if list.is_empty() {
+                    ui.label(format!("{} has not been saved yet.", self.current_game));
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("revisions grid")
+                            .num_columns(2)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for i in list.iter() {
+                                    ui.label(
+                                        i.file_stem().unwrap_or_default().to_string_lossy(),
+                                    );
+                                    if ui.button("Restore").clicked() {
+                                        match revisions::load_revision(i) {
+                                            Err(e) => {
+                                                self.set_modal(
+                                                    format!(
+                                                        "Error occurred while reading data from {}:\n{}",
+                                                        i.display(),
+                                                        e
+                                                    ),
+                                                    ModalStatus::Error,
+                                                );
+                                            }
+                                            Result::Ok(config) => {
+                                                self.shared_state.temp_config = config;
+                                                self.shared_state.first_run[2] = true;
+                                            }
                                         }
+                                        self.shared_state.revisions = None;
                                     }
+                                    ui.end_row();
                                 }
-                            }
-                            ui.end_row();
-                            if cl.has_evdev() {
-                                let mut_evdev_keymap = self
-                                    .shared_state
-                                    .temp_config
-                                    .input_method
-                                    .get_evdev_mut()
-                                    .unwrap();
-                                ui.strong("Evdev Keymap:");
-                                ui.end_row();
-                                ui.label("Test Key:");
-                                ui.text_edit_singleline(
-                                    mut_evdev_keymap.player1.test.as_mut().unwrap(),
-                                );
-                                ui.end_row();
-                                ui.label("Player 1 Start Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player1.start);
-                                ui.end_row();
-                                ui.label("Player 1 Service Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player1.service);
-                                ui.end_row();
-                                ui.label("Player 1 Up Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player1.up);
-                                ui.end_row();
-                                ui.label("Player 1 Down Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player1.down);
-                                ui.end_row();
-                                ui.label("Player 1 Left Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player1.left);
-                                ui.end_row();
-                                ui.label("Player 1 Right Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player1.right);
-                                ui.end_row();
-                                ui.label("Player 1 Button 1 Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player1.button1);
-                                ui.end_row();
-                                ui.label("Player 1 Button 2 Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player1.button2);
-                                ui.end_row();
-                                ui.label("Player 1 Button 3 Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player1.button3);
-                                ui.end_row();
-                                ui.label("Player 1 Button 4 Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player1.button4);
-                                ui.end_row();
-                                ui.label("Player 1 Button 5 Key:");
-                                ui.text_edit_singleline(
-                                    mut_evdev_keymap.player1.button5.as_mut().unwrap(),
-                                );
-                                ui.end_row();
-                                ui.label("Player 1 Button 6 Key:");
-                                ui.text_edit_singleline(
-                                    mut_evdev_keymap.player1.button6.as_mut().unwrap(),
-                                );
-                                ui.end_row();
-                                ui.label("Player 1 Button 7 Key:");
-                                ui.text_edit_singleline(
-                                    mut_evdev_keymap.player1.button7.as_mut().unwrap(),
-                                );
-                                ui.end_row();
-                                ui.label("Player 1 Button 8 Key:");
-                                ui.text_edit_singleline(
-                                    mut_evdev_keymap.player1.button8.as_mut().unwrap(),
-                                );
-                                ui.end_row();
-                                ui.label("Player 2 Start Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player2.start);
-                                ui.end_row();
-                                ui.label("Player 2 Service Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player2.service);
-                                ui.end_row();
-                                ui.label("Player 2 Up Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player2.up);
-                                ui.end_row();
-                                ui.label("Player 2 Down Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player2.down);
-                                ui.end_row();
-                                ui.label("Player 2 Left Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player2.left);
-                                ui.end_row();
-                                ui.label("Player 2 Right Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player2.right);
-                                ui.end_row();
-                                ui.label("Player 2 Button 1 Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player2.button1);
-                                ui.end_row();
-                                ui.label("Player 2 Button 2 Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player2.button2);
-                                ui.end_row();
-                                ui.label("Player 2 Button 3 Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player2.button3);
-                                ui.end_row();
-                                ui.label("Player 2 Button 4 Key:");
-                                ui.text_edit_singleline(&mut mut_evdev_keymap.player2.button4);
-                                ui.end_row();
-                                ui.label("Player 2 Button 5 Key:");
-                                ui.text_edit_singleline(
-                                    mut_evdev_keymap.player2.button5.as_mut().unwrap(),
-                                );
-                                ui.end_row();
-                                ui.label("Player 2 Button 6 Key:");
-                                ui.text_edit_singleline(
-                                    mut_evdev_keymap.player2.button6.as_mut().unwrap(),
-                                );
-                                ui.end_row();
-                                ui.label("Player 2 Button 7 Key:");
-                                ui.text_edit_singleline(
-                                    mut_evdev_keymap.player2.button7.as_mut().unwrap(),
-                                );
-                                ui.end_row();
-                                ui.label("Player 2 Button 8 Key:");
-                                ui.text_edit_singleline(
-                                    mut_evdev_keymap.player2.button8.as_mut().unwrap(),
-                                );
-                                ui.end_row();
-                            }
-                        });
+                            });
                     });
+                ui.separator();
+                ui.vertical_centered(|ui| {
+                    let close = ui.button("close");
+                    focus_on_open(ui, &close);
+                    if close.clicked() {
+                        self.shared_state.revisions = None;
+                    }
                 });
+            });
+            if modal.should_close() {
+                self.shared_state.revisions = None;
+            }
+        }
+    }
+}
+impl LoaderApp {
+    fn gpu_update(&mut self, ctx: &egui::Context) {
+        if self.gpu.is_some() {
+            return;
+        }
+        let Some(detection) = &self.gpu_detection else {
+            self.gpu_detection = Some(GpuReport::detect_in_background());
+            ctx.request_repaint_after(Duration::from_millis(100));
+            return;
+        };
+        match detection.try_recv() {
+            Err(TryRecvError::Empty) => {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+            Err(TryRecvError::Disconnected) => {
+                self.gpu = Some(GpuReport::default());
+                self.gpu_detection = None;
+            }
+            Result::Ok(report) => {
+                self.gpu = Some(report);
+                self.gpu_detection = None;
+            }
+        }
+    }
+    // Waits for the detection when a game is launched before it finished
+    fn gpu_report(&mut self) -> &GpuReport {
+        if let Some(detection) = self.gpu_detection.take() {
+            self.gpu = Some(detection.recv().unwrap_or_default());
+        }
+        self.gpu.get_or_insert_with(GpuReport::detect)
+    }
+    fn launch_game(&mut self, test_mode: bool) {
+        if self.current_game.as_gamedata().not_working_on_ati && self.gpu_report().is_amd() {
+            self.ati_warning = Some((test_mode, self.current_game.clone()));
+            return;
+        }
+        self.check_link_and_run(test_mode);
+    }
+    fn ati_warning_update(&mut self, ctx: &egui::Context) {
+        let Some((test_mode, game)) = self.ati_warning.clone() else {
+            return;
+        };
+        let gpu = self.gpu_report().describe();
+        let mut launch = false;
+        let modal = Modal::new(egui::Id::new("ATI Warning Modal")).show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.colored_label(
+                    Color32::from_rgb(255, 165, 0),
+                    RichText::new("Warning").strong().size(25.0),
+                );
+            });
+            ui.separator();
+            ui.label(format!(
+                "{} is known not to work on AMD/ATI graphics.\nDetected GPU: {}",
+                game.as_gamedata().game_title,
+                gpu
+            ));
+            ui.separator();
+            ui.horizontal(|ui| {
+                let button = ui.button("Launch Anyway");
+                focus_on_open(ui, &button);
+                launch = button.clicked();
+                if ui.button("Cancel").clicked() {
+                    self.ati_warning = None;
+                }
+            });
         });
-        egui::TopBottomPanel::bottom("config mapping btm panel").show(ctx, |ui| {
-            egui_alignments::center_horizontal(ui, |ui| {
-                if ui.button("Save").clicked() {
-                    *self.get_config_mut() = self.shared_state.temp_config.clone();
-                    self.shared_state.first_run = [true; 4];
-                    if let Err(e) = self
-                        .get_config()
-                        .write_to_lindbergh_conf(&self.current_game)
-                    {
-                        self.set_modal(
-                            format!("Error occurred while writing data \"{}\"", e),
-                            ModalStatus::Error,
-                        );
-                    } else {
-                        self.set_modal(
-                            format!(
-                                "Configuration successfully saved into ./config/{:?}.conf",
-                                self.current_game
-                            ),
-                            ModalStatus::Info,
-                        );
-                        self.shared_state.shared_text = Default::default();
-                        self.app_state = AppState::MainPage;
-                        self.shared_state.temp_config.input_method = Keymap::default();
-                        self.shared_state.new_game_modify = None;
+        if modal.should_close() {
+            self.ati_warning = None;
+        }
+        if launch {
+            self.ati_warning = None;
+            self.current_game = game;
+            self.check_link_and_run(test_mode);
+        }
+    }
+    // Linked games go through the preflight dialog first
+    fn check_link_and_run(&mut self, test_mode: bool) {
+        let Some(kind) = LinkKind::from_title(&self.current_game) else {
+            self.run_game_with_monitor(test_mode);
+            return;
+        };
+        if let Err(e) = self
+            .shared_state
+            .temp_config
+            .read_from_lindbergh_conf_by_title(&self.current_game)
+        {
+            self.set_modal(
+                format!("Error occurred while reading data \"{}\"", e),
+                ModalStatus::Error,
+            );
+            return;
+        }
+        // Answers the echo of the other cabinets' preflight checks
+        if self.discovery.is_none() {
+            self.discovery = Discovery::start_lan().ok();
+        }
+        self.preflight_check = Some((
+            test_mode,
+            Preflight::run_in_background(
+                kind,
+                self.shared_state.temp_config.clone(),
+                discovery::DISCOVERY_PORT,
+            ),
+        ));
+    }
+    fn preflight_check_update(&mut self, ctx: &egui::Context) {
+        let Some((test_mode, check)) = &self.preflight_check else {
+            return;
+        };
+        let test_mode = *test_mode;
+        match check.try_recv() {
+            Err(TryRecvError::Empty) => {
+                ctx.request_repaint_after(Duration::from_millis(100));
+                let mut cancel = false;
+                let modal = Modal::new(egui::Id::new("Preflight Check Modal")).show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Checking the link...");
+                    });
+                    ui.separator();
+                    ui.vertical_centered(|ui| {
+                        let button = ui.button("Cancel");
+                        focus_on_open(ui, &button);
+                        cancel = button.clicked();
+                    });
+                });
+                if cancel || modal.should_close() {
+                    self.preflight_check = None;
+                    self.discovery = None;
+                }
+            }
+            Err(TryRecvError::Disconnected) => {
+                self.preflight_check = None;
+                self.discovery = None;
+                self.set_modal("The link checks stopped unexpectedly", ModalStatus::Error);
+            }
+            Result::Ok(preflight) => {
+                self.preflight_check = None;
+                if preflight.passed() {
+                    self.run_game_with_monitor(test_mode);
+                    self.discovery = None;
+                } else {
+                    self.preflight = Some((test_mode, preflight));
+                }
+            }
+        }
+    }
+    fn preflight_update(&mut self, ctx: &egui::Context) {
+        self.preflight_check_update(ctx);
+        let Some((test_mode, preflight)) = &self.preflight else {
+            return;
+        };
+        let test_mode = *test_mode;
+        // Some(true) to launch,Some(false) to check again
+        let mut action = None;
+        let mut cancel = false;
+        let modal = Modal::new(egui::Id::new("Preflight Modal")).show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label(RichText::new("Link Preflight").strong().size(25.0));
+            });
+            ui.separator();
+            egui::Grid::new("preflight grid")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for i in &preflight.checks {
+                        match i.status {
+                            CheckStatus::Pass => {
+                                ui.colored_label(Color32::from_rgb(0, 128, 0), "OK");
+                            }
+                            CheckStatus::Warn => {
+                                ui.colored_label(Color32::from_rgb(255, 165, 0), "Warning");
+                            }
+                            CheckStatus::Fail => {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Failed");
+                            }
+                        }
+                        ui.strong(&i.name);
+                        ui.label(&i.detail);
+                        ui.end_row();
                     }
+                });
+            if preflight.failed() {
+                ui.label("The game is likely to hang on its link screen.");
+            }
+            ui.weak("The ports the game links on are not known,so they are not checked.");
+            ui.separator();
+            ui.horizontal(|ui| {
+                let check = ui.button("Check Again");
+                focus_on_open(ui, &check);
+                if check.clicked() {
+                    action = Some(false);
+                }
+                if ui.button("Launch Anyway").clicked() {
+                    action = Some(true);
                 }
                 if ui.button("Cancel").clicked() {
-                    self.shared_state.shared_text = Default::default();
-                    self.app_state = AppState::MainPage;
-                    self.shared_state.temp_config.input_method = Keymap::default();
-                    self.shared_state.first_run = [true; 4];
-                    self.shared_state.new_game_modify = None;
+                    cancel = true;
                 }
             });
         });
+        if cancel || modal.should_close() {
+            self.preflight = None;
+            self.discovery = None;
+        }
+        match action {
+            None => {}
+            Some(false) => {
+                self.preflight = None;
+                self.check_link_and_run(test_mode);
+            }
+            Some(true) => {
+                self.preflight = None;
+                self.run_game_with_monitor(test_mode);
+                self.discovery = None;
+            }
+        }
     }
-}
-
-impl LoaderApp {
     fn run_game_with_monitor(&mut self, test_mode: bool) {
         if let Err(e) = self
             .shared_state
@@ -1305,13 +3603,37 @@ impl LoaderApp {
             );
             return;
         }
+        let target = self.shared_state.temp_config.display.clone();
+        let outputs = if target.output.is_empty() {
+            vec![]
+        } else {
+            display::list_outputs().unwrap_or_default()
+        };
+        let restore = match target.apply(&outputs) {
+            Err(e) => {
+                self.set_modal(
+                    format!("Unable to switch the display mode:\n{}", e),
+                    ModalStatus::Error,
+                );
+                return;
+            }
+            Result::Ok(k) => k,
+        };
         let exe_path = &self.shared_state.temp_config.exe_path;
-        match run_game(exe_path.as_str(), test_mode, &self.current_game) {
+        match run_game(
+            exe_path.as_str(),
+            test_mode,
+            &self.current_game,
+            &target.env(&outputs),
+        ) {
             Err(e) => {
                 self.set_modal(
                     format!("Error occurred while running game:\n{}", e),
                     ModalStatus::Error,
                 );
+                if let Some(mode) = restore {
+                    let _ = display::set_mode(&target.output, mode);
+                }
                 return;
             }
             Result::Ok(c) => {
@@ -1339,11 +3661,65 @@ impl LoaderApp {
             }
         }
         self.current_process = None;
+        // Pad presses meant for the game are still queued,drop them before they reach the UI
+        if let Some(nav) = &self.gamepad_nav {
+            nav.drain();
+        }
+        if let Some(mode) = restore
+            && let Err(e) = display::set_mode(&target.output, mode)
+        {
+            self.set_modal(
+                format!(
+                    "Unable to switch {} back to {}:\n{}",
+                    target.output, mode, e
+                ),
+                ModalStatus::Error,
+            );
+        }
     }
 }
 impl eframe::App for LoaderApp {
+    // Gamepad presses are fed to egui as keys,so every page can be driven like a keyboard
+    fn raw_input_hook(&mut self, ctx: &egui::Context, raw_input: &mut egui::RawInput) {
+        let nav = self
+            .gamepad_nav
+            .get_or_insert_with(|| GamepadNav::start(&evdev::list_devices().unwrap_or_default()));
+        if nav.needs_rescan() {
+            nav.rescan(&evdev::list_devices().unwrap_or_default());
+        }
+        if nav.is_empty() {
+            // Wake up now and then to notice a pad being plugged in
+            ctx.request_repaint_after(gamepad::RESCAN_INTERVAL);
+            return;
+        }
+        for i in nav.poll() {
+            let key = match i {
+                NavAction::Up => Key::ArrowUp,
+                NavAction::Down => Key::ArrowDown,
+                NavAction::Left => Key::ArrowLeft,
+                NavAction::Right => Key::ArrowRight,
+                NavAction::Accept => Key::Enter,
+                NavAction::Back => Key::Escape,
+            };
+            for pressed in [true, false] {
+                raw_input.events.push(egui::Event::Key {
+                    key,
+                    physical_key: None,
+                    pressed,
+                    repeat: false,
+                    modifiers: Modifiers::NONE,
+                });
+            }
+        }
+        // Gamepad events don't wake egui up
+        ctx.request_repaint_after(Duration::from_millis(100));
+    }
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.modal_update(ctx);
+        self.preflight_update(ctx);
+        self.ati_warning_update(ctx);
+        self.gpu_update(ctx);
+        self.loader_info_update();
         self.game_library_update();
         match self.app_state {
             AppState::MainPage => {
@@ -1358,193 +3734,85 @@ impl eframe::App for LoaderApp {
             AppState::ConfigureMapping => {
                 self.configure_mapping_ui(ctx);
             }
+            AppState::ConfigureGun => {
+                self.configure_gun_ui(ctx);
+            }
+            AppState::JvsDiagnostics => {
+                self.jvs_diagnostics_ui(ctx);
+            }
+            AppState::SerialConsole => {
+                self.serial_console_ui(ctx);
+            }
+            AppState::CardManager => {
+                self.card_manager_ui(ctx);
+            }
+            AppState::LinkWizard => {
+                self.link_wizard_ui(ctx);
+            }
         }
     }
 }
 
-// NOTE: F13~F35 will not be mapped because I don't know in which universe keyboard has these keys
-
-pub fn egui_key_to_keycode(key: &egui::Key) -> Option<u32> {
-    let zero = key.name().chars().next().unwrap();
-    if zero.is_numeric() {
-        if zero == '0' {
-            return Some(19);
-        } else {
-            return Some(((zero as u8) - 39) as u32);
-        }
-    }
-    if zero.is_alphabetic() && key.name().len() == 1 {
-        return match zero {
-            'Q' => Some(24),
-            'W' => Some(25),
-            'E' => Some(26),
-            'R' => Some(27),
-            'T' => Some(28),
-            'Y' => Some(29),
-            'U' => Some(30),
-            'I' => Some(31),
-            'O' => Some(32),
-            'P' => Some(33),
-            'A' => Some(38),
-            'S' => Some(39),
-            'D' => Some(40),
-            'F' => Some(41),
-            'G' => Some(42),
-            'H' => Some(43),
-            'J' => Some(44),
-            'K' => Some(45),
-            'L' => Some(46),
-            'Z' => Some(52),
-            'X' => Some(53),
-            'C' => Some(54),
-            'V' => Some(55),
-            'B' => Some(56),
-            'N' => Some(57),
-            'M' => Some(58),
-            _ => None,
-        };
-    }
-    if zero == 'F' && key.name().len() > 1 {
-        return match key {
-            Key::F1 => Some(67),
-            Key::F2 => Some(68),
-            Key::F3 => Some(69),
-            Key::F4 => Some(70),
-            Key::F5 => Some(71),
-            Key::F6 => Some(72),
-            Key::F7 => Some(73),
-            Key::F8 => Some(74),
-            Key::F9 => Some(75),
-            Key::F10 => Some(76),
-            Key::F11 => Some(95),
-            Key::F12 => Some(96),
-            _ => None,
-        };
-    }
-    match key {
-        Key::Minus => Some(20),
-        Key::Equals => Some(21),
-        Key::Backspace => Some(22),
-        Key::Tab => Some(23),
-        Key::Semicolon => Some(47),
-        Key::Quote => Some(48),
-        Key::Backtick => Some(49),
-        Key::Backslash => Some(51),
-        Key::Comma => Some(59),
-        Key::Period => Some(60),
-        Key::Slash => Some(61),
-        Key::Space => Some(65),
-        Key::Home => Some(110),
-        Key::ArrowUp => Some(111),
-        Key::PageUp => Some(112),
-        Key::ArrowLeft => Some(113),
-        Key::ArrowRight => Some(114),
-        Key::End => Some(115),
-        Key::ArrowDown => Some(116),
-        Key::PageDown => Some(117),
-        Key::Insert => Some(118),
-        Key::Delete => Some(119),
-        Key::Escape => Some(9),
-        Key::Enter => Some(36),
-        Key::Colon => Some(47),
-        Key::Plus => Some(21),
-        Key::OpenBracket => Some(34),
-        Key::CloseBracket => Some(35),
-        Key::Pipe => Some(51),
-        Key::Questionmark => Some(61),
-        Key::Copy => Some(141),
-        Key::Paste => Some(144),
-        Key::Cut => Some(145),
-        _ => None,
+// Red when the key is also bound elsewhere
+fn binding_label(
+    ui: &mut egui::Ui,
+    template: &InputTemplate,
+    slot: BindingSlot,
+    conflicts: &[BindingSlot],
+) {
+    let text = if slot.field == KeymapField::Test {
+        "Test Key:".to_string()
+    } else {
+        format!(
+            "Player {} {} Key:",
+            slot.player,
+            template.label(slot.field).unwrap_or(slot.field.to_string())
+        )
+    };
+    if conflicts.is_empty() {
+        ui.label(text);
+    } else {
+        ui.colored_label(Color32::from_rgb(255, 0, 0), text)
+            .on_hover_text(format!(
+                "Also bound to:\n{}",
+                conflicts
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            ));
     }
 }
-// Thanks chatgpt
-pub fn egui_keycode_to_key(keycode: u32) -> Option<egui::Key> {
-    match keycode {
-        // Numeric keys
-        19 => Some(Key::Num0),
-        10 => Some(Key::Num1),
-        11 => Some(Key::Num2),
-        12 => Some(Key::Num3),
-        13 => Some(Key::Num4),
-        14 => Some(Key::Num5),
-        15 => Some(Key::Num6),
-        16 => Some(Key::Num7),
-        17 => Some(Key::Num8),
-        18 => Some(Key::Num9),
-        20 => Some(Key::Minus),
-        21 => Some(Key::Equals),
-        // Alphabetic keys
-        24 => Some(Key::Q),
-        25 => Some(Key::W),
-        26 => Some(Key::E),
-        27 => Some(Key::R),
-        28 => Some(Key::T),
-        29 => Some(Key::Y),
-        30 => Some(Key::U),
-        31 => Some(Key::I),
-        32 => Some(Key::O),
-        33 => Some(Key::P),
-        38 => Some(Key::A),
-        39 => Some(Key::S),
-        40 => Some(Key::D),
-        41 => Some(Key::F),
-        42 => Some(Key::G),
-        43 => Some(Key::H),
-        44 => Some(Key::J),
-        45 => Some(Key::K),
-        46 => Some(Key::L),
-        52 => Some(Key::Z),
-        53 => Some(Key::X),
-        54 => Some(Key::C),
-        55 => Some(Key::V),
-        56 => Some(Key::B),
-        57 => Some(Key::N),
-        58 => Some(Key::M),
-        // Function keys
-        67 => Some(Key::F1),
-        68 => Some(Key::F2),
-        69 => Some(Key::F3),
-        70 => Some(Key::F4),
-        71 => Some(Key::F5),
-        72 => Some(Key::F6),
-        73 => Some(Key::F7),
-        74 => Some(Key::F8),
-        75 => Some(Key::F9),
-        76 => Some(Key::F10),
-        95 => Some(Key::F11),
-        96 => Some(Key::F12),
-        // Punctuation keys
-        34 => Some(Key::OpenBracket),
-        35 => Some(Key::CloseBracket),
-        47 => Some(Key::Semicolon),
-        48 => Some(Key::Quote),
-        49 => Some(Key::Backtick),
-        51 => Some(Key::Backslash),
-        59 => Some(Key::Comma),
-        60 => Some(Key::Period),
-        61 => Some(Key::Slash),
-        65 => Some(Key::Space),
-        // Navigation keys
-        9 => Some(Key::Escape),
-        22 => Some(Key::Backspace),
-        23 => Some(Key::Tab),
-        36 => Some(Key::Enter),
-        110 => Some(Key::Home),
-        111 => Some(Key::ArrowUp),
-        112 => Some(Key::PageUp),
-        113 => Some(Key::ArrowLeft),
-        114 => Some(Key::ArrowRight),
-        115 => Some(Key::End),
-        116 => Some(Key::ArrowDown),
-        117 => Some(Key::PageDown),
-        118 => Some(Key::Insert),
-        119 => Some(Key::Delete),
-        // Clipboard actions
-        141 => Some(Key::Copy),
-        144 => Some(Key::Paste),
-        145 => Some(Key::Cut),
-        // Default case
-        _ => None,
+
+// The axis' seen range,with the deadzones shaded and a line at the current value
+fn axis_bar(ui: &mut egui::Ui, state: Option<AxisState>, deadzone: (u32, u32, u32)) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(200.0, 16.0), egui::Sense::hover());
+    let painter = ui.painter();
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    let Some(state) = state else {
+        return;
+    };
+    let span = (state.max - state.min).max(1) as f32;
+    let x = |v: f32| rect.left() + (v / span).clamp(0.0, 1.0) * rect.width();
+    let shade = Color32::from_rgba_unmultiplied(255, 80, 80, 80);
+    let (min, center, max) = (deadzone.0 as f32, deadzone.1 as f32, deadzone.2 as f32);
+    for (start, end) in [
+        (0.0, min),
+        (span / 2.0 - center, span / 2.0 + center),
+        (span - max, span),
+    ] {
+        painter.rect_filled(
+            egui::Rect::from_x_y_ranges(x(start)..=x(end), rect.y_range()),
+            0.0,
+            shade,
+        );
     }
+    let value = x((state.value - state.min) as f32);
+    painter.line_segment(
+        [
+            egui::pos2(value, rect.top()),
+            egui::pos2(value, rect.bottom()),
+        ],
+        egui::Stroke::new(2.0_f32, ui.visuals().strong_text_color()),
+    );
 }